@@ -0,0 +1,102 @@
+use crate::types::Type;
+
+// Classification of struct and union values in the SysV x86-64 calling convention.
+// A value of at most 16 bytes is split into eightbytes, and each eightbyte is
+// passed in a general purpose register (INTEGER) or an SSE register (SSE).
+// Anything else is passed in memory, except that a returned value of only a long
+// double (X87 and X87UP) is returned in %st0.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgClass {
+    Integer(i32),   // size in bytes
+    Sse(i32, bool), // size in bytes, whether it contains a double
+}
+
+// classify the eightbytes of a struct or union, or return None if it is passed in memory
+pub fn classify(ty: &Type) -> Option<Vec<ArgClass>> {
+    let size = ty.size();
+    if size == 0 || size > 16 {
+        return None;
+    }
+    let cnt = ((size + 7) / 8) as usize;
+    let mut has_int = vec![false; cnt];
+    let mut has_float = vec![false; cnt];
+    let mut has_double = vec![false; cnt];
+    if !classify_leaves(ty, 0, &mut has_int, &mut has_float, &mut has_double) {
+        return None;
+    }
+    let mut classes = Vec::new();
+    for i in 0..cnt {
+        let bytes = (size - 8 * i as i32).min(8);
+        // an eightbyte is SSE only if every scalar in it is floating
+        if !has_int[i] && (has_float[i] || has_double[i]) {
+            classes.push(ArgClass::Sse(bytes, has_double[i]));
+        } else {
+            classes.push(ArgClass::Integer(bytes));
+        }
+    }
+    Some(classes)
+}
+
+// whether a returned struct or union is of the X87 class, i.e. its only scalar is a long double
+pub fn is_x87(ty: &Type) -> bool {
+    ty.size() == 16 && is_long_double_at(ty, 0)
+}
+
+fn is_long_double_at(ty: &Type, offset: i32) -> bool {
+    match ty.strip_qual() {
+        Type::Struct(st) => st
+            .borrow()
+            .members
+            .iter()
+            .all(|member| is_long_double_at(&member.ty, offset + member.offset)),
+        Type::Array(elem_ty, len) => {
+            (0..(*len).max(0)).all(|i| is_long_double_at(elem_ty, offset + i * elem_ty.size()))
+        }
+        Type::LongDouble => offset == 0,
+        _ => false,
+    }
+}
+
+// walk the scalars contained in ty, recording which eightbytes they occupy;
+// returns false if ty contains a long double (X87 class), which is passed in memory
+fn classify_leaves(
+    ty: &Type,
+    offset: i32,
+    has_int: &mut Vec<bool>,
+    has_float: &mut Vec<bool>,
+    has_double: &mut Vec<bool>,
+) -> bool {
+    match ty.strip_qual() {
+        Type::Struct(st) => {
+            for member in &st.borrow().members {
+                if !classify_leaves(&member.ty, offset + member.offset, has_int, has_float, has_double) {
+                    return false;
+                }
+            }
+            true
+        }
+        Type::Array(elem_ty, len) => {
+            for i in 0..(*len).max(0) {
+                let elem_offset = offset + i * elem_ty.size();
+                if !classify_leaves(elem_ty, elem_offset, has_int, has_float, has_double) {
+                    return false;
+                }
+            }
+            true
+        }
+        Type::LongDouble => false,
+        Type::Float => {
+            has_float[(offset / 8) as usize] = true;
+            true
+        }
+        Type::Double => {
+            has_double[(offset / 8) as usize] = true;
+            true
+        }
+        _ => {
+            has_int[(offset / 8) as usize] = true;
+            true
+        }
+    }
+}
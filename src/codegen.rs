@@ -1,6 +1,7 @@
 extern crate llvm_sys as llvm;
 use self::llvm::core::*;
 use self::llvm::prelude::*;
+use crate::abi;
 use crate::node;
 use crate::types;
 /*
@@ -12,11 +13,13 @@ use llvm::execution_engine::LLVMGetFunctionAddress;
 use llvm::execution_engine::LLVMLinkInMCJIT;
 use std::mem;
 */
-use node::{BinaryOps, UnaryOps, AST};
+use node::{BinaryOps, InitLeaf, UnaryOps, VarAttr, AST};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::ptr;
-use types::Type;
+use std::rc::Rc;
+use types::{BitField, Qualifiers, StructDef, Type};
 
 #[derive(Debug)]
 pub struct VarInfo {
@@ -33,12 +36,31 @@ impl VarInfo {
     }
 }
 
+// how a parameter or a return value is passed in the SysV x86-64 calling convention
+#[derive(Debug, Clone)]
+pub enum ArgKind {
+    Direct,                    // as the LLVM value of its type
+    Coerced(Vec<LLVMTypeRef>), // struct in registers, as one LLVM value per eightbyte
+    Memory,                    // struct on the stack (byval), or returned through a hidden pointer (sret)
+}
+
+#[derive(Debug, Clone)]
+pub struct FuncAbi {
+    ret: ArgKind,
+    params: Vec<ArgKind>,
+}
+
 pub struct Codegen {
     context: LLVMContextRef,
     module: LLVMModuleRef,
     builder: LLVMBuilderRef,
     cur_func: Option<LLVMValueRef>,
+    cur_func_abi: Option<FuncAbi>,
+    cur_sret: Option<LLVMValueRef>,
+    global_varmap: HashMap<String, VarInfo>,
     local_varmap: Vec<HashMap<String, VarInfo>>,
+    struct_types: HashMap<usize, LLVMTypeRef>, // struct id -> LLVM struct type
+    struct_fields: HashMap<usize, Vec<u32>>,   // struct id -> LLVM field index of each member
 }
 
 pub unsafe fn cstr(s: &'static str) -> CString {
@@ -46,62 +68,351 @@ pub unsafe fn cstr(s: &'static str) -> CString {
 }
 
 pub unsafe fn is_exist_terminator(builder: LLVMBuilderRef) -> bool {
-    LLVMIsATerminatorInst(LLVMGetLastInstruction(LLVMGetInsertBlock(builder))) != ptr::null_mut()
-}
-
-pub unsafe fn inside_load(ast: &AST) -> &AST {
-    match ast {
-        AST::Load(node) => node,
-        _ => panic!("Error: ast load"),
-    }
+    let last_inst = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+    // an empty basic block has no terminator
+    !last_inst.is_null() && !LLVMIsATerminatorInst(last_inst).is_null()
 }
 
 impl Codegen {
     pub unsafe fn new(mod_name: &str) -> Codegen {
         let c_mod_name = CString::new(mod_name).unwrap();
+        // types are created in the global context (e.g. LLVMInt32Type()),
+        // so the module and the builder have to live in it too
+        let context = LLVMGetGlobalContext();
+        let module = LLVMModuleCreateWithNameInContext(c_mod_name.as_ptr(), context);
+        LLVMSetTarget(module, cstr(types::TARGET.triple).as_ptr());
+        LLVMSetDataLayout(module, cstr(types::TARGET.data_layout).as_ptr());
         Codegen {
-            context: LLVMContextCreate(),
-            module: LLVMModuleCreateWithNameInContext(c_mod_name.as_ptr(), LLVMContextCreate()),
-            builder: LLVMCreateBuilderInContext(LLVMContextCreate()),
+            context,
+            module,
+            builder: LLVMCreateBuilderInContext(context),
             cur_func: None,
+            cur_func_abi: None,
+            cur_sret: None,
+            global_varmap: HashMap::new(),
             local_varmap: Vec::new(),
+            struct_types: HashMap::new(),
+            struct_fields: HashMap::new(),
         }
     }
 
-    // TODO: convert type
-    pub unsafe fn typecast(&mut self, val: LLVMValueRef, to: LLVMTypeRef) -> LLVMValueRef {
-        let from = LLVMTypeOf(val);
-        match LLVMGetTypeKind(from) {
-            llvm::LLVMTypeKind::LLVMPointerTypeKind => match LLVMGetTypeKind(to) {
-                llvm::LLVMTypeKind::LLVMIntegerTypeKind => {
-                    return LLVMBuildPtrToInt(self.builder, val, to, cstr("cast").as_ptr());
-                }
-                _ => panic!(),
-            },
-            _ => val,
+    pub unsafe fn typecast(&mut self, val: LLVMValueRef, from: &Type, to: &Type) -> LLVMValueRef {
+        let llvm_to = self.type_to_llvmty(to);
+        if to.is_bool() {
+            // conversion to _Bool yields 0 if the value compares equal to 0, and 1 otherwise
+            let zero = LLVMConstNull(LLVMTypeOf(val));
+            let cmp = if from.is_flonum() {
+                LLVMBuildFCmp(
+                    self.builder,
+                    llvm::LLVMRealPredicate::LLVMRealUNE,
+                    val,
+                    zero,
+                    cstr("tobool").as_ptr(),
+                )
+            } else {
+                LLVMBuildICmp(
+                    self.builder,
+                    llvm::LLVMIntPredicate::LLVMIntNE,
+                    val,
+                    zero,
+                    cstr("tobool").as_ptr(),
+                )
+            };
+            LLVMBuildZExt(self.builder, cmp, llvm_to, cstr("zext").as_ptr())
+        } else if from.is_integer() && to.is_integer() {
+            // chooses trunc, sext or zext by the widths and the signedness of the source
+            let is_signed = !from.is_unsigned() as i32;
+            LLVMBuildIntCast2(self.builder, val, llvm_to, is_signed, cstr("cast").as_ptr())
+        } else if from.is_integer() && to.is_flonum() {
+            if from.is_unsigned() {
+                LLVMBuildUIToFP(self.builder, val, llvm_to, cstr("uitofp").as_ptr())
+            } else {
+                LLVMBuildSIToFP(self.builder, val, llvm_to, cstr("sitofp").as_ptr())
+            }
+        } else if from.is_flonum() && to.is_integer() {
+            if to.is_unsigned() {
+                LLVMBuildFPToUI(self.builder, val, llvm_to, cstr("fptoui").as_ptr())
+            } else {
+                LLVMBuildFPToSI(self.builder, val, llvm_to, cstr("fptosi").as_ptr())
+            }
+        } else if from.is_flonum() && to.is_flonum() {
+            LLVMBuildFPCast(self.builder, val, llvm_to, cstr("fpcast").as_ptr())
+        } else if from.is_ptr() && to.is_integer() {
+            LLVMBuildPtrToInt(self.builder, val, llvm_to, cstr("ptrtoint").as_ptr())
+        } else if from.is_integer() && to.is_ptr() {
+            // extend the integer to the width of a pointer as a conversion to long does
+            let is_signed = !from.is_unsigned() as i32;
+            let val = LLVMBuildIntCast2(self.builder, val, LLVMInt64Type(), is_signed, cstr("cast").as_ptr());
+            LLVMBuildIntToPtr(self.builder, val, llvm_to, cstr("inttoptr").as_ptr())
+        } else if from.is_ptr() && to.is_ptr() {
+            LLVMBuildPointerCast(self.builder, val, llvm_to, cstr("ptrcast").as_ptr())
+        } else {
+            panic!("Cannot convert {} to {}", from, to);
         }
     }
 
-    pub unsafe fn type_to_llvmty(&self, ty: &Type) -> LLVMTypeRef {
+    pub unsafe fn type_to_llvmty(&mut self, ty: &Type) -> LLVMTypeRef {
         match &ty {
-            Type::Int => LLVMInt32Type(),
+            Type::Void => LLVMVoidType(),
+            // _Bool is kept as i8 holding 0 or 1, which is also its representation in memory
+            Type::Bool => LLVMInt8Type(),
+            Type::Char | Type::UChar => LLVMInt8Type(),
+            Type::Short | Type::UShort => LLVMInt16Type(),
+            Type::Int | Type::UInt => LLVMInt32Type(),
+            Type::Long | Type::ULong | Type::LongLong | Type::ULongLong => LLVMInt64Type(),
+            Type::Float => LLVMFloatType(),
+            Type::Double => LLVMDoubleType(),
+            // long double is the 80-bit x87 format on x86-64
+            Type::LongDouble => LLVMX86FP80Type(),
+            // LLVM has no pointer to void, so void * is lowered to i8 *
+            Type::Ptr(basety) if basety.is_void() => LLVMPointerType(LLVMInt8Type(), 0),
             Type::Ptr(basety) => LLVMPointerType(self.type_to_llvmty(&*basety), 0),
-            Type::Func(ret_type, param_types, _) => LLVMFunctionType(
-                self.type_to_llvmty(ret_type),
-                || -> *mut LLVMTypeRef {
-                    let mut param_llvm_types: Vec<LLVMTypeRef> = Vec::new();
-                    for param_type in param_types {
-                        param_llvm_types.push(self.type_to_llvmty(param_type));
+            Type::Array(elem_ty, len) => {
+                LLVMArrayType(self.type_to_llvmty(elem_ty), (*len).max(0) as u32)
+            }
+            // a variable length array is addressed as a pointer to its innermost elements,
+            // and indexed by scaling with its run-time length
+            Type::Vla(elem_ty, _) => self.type_to_llvmty(elem_ty),
+            Type::Func(..) => self.func_to_llvmty(ty),
+            Type::Struct(st) => self.struct_to_llvmty(st),
+            // qualifiers do not change the representation
+            Type::Qual(ty, _) => self.type_to_llvmty(ty),
+        }
+    }
+
+    unsafe fn struct_to_llvmty(&mut self, st: &Rc<RefCell<StructDef>>) -> LLVMTypeRef {
+        let id = st.borrow().id;
+        let llvm_ty = match self.struct_types.get(&id) {
+            Some(llvm_ty) => *llvm_ty,
+            None => {
+                let st = st.borrow();
+                let name = format!(
+                    "{}.{}",
+                    if st.is_union { "union" } else { "struct" },
+                    st.tag.clone().unwrap_or_else(|| "anon".to_string())
+                );
+                let name = CString::new(name).unwrap();
+                let llvm_ty = LLVMStructCreateNamed(self.context, name.as_ptr());
+                self.struct_types.insert(id, llvm_ty);
+                llvm_ty
+            }
+        };
+        // an incomplete type stays opaque until it is completed
+        if !st.borrow().is_complete || self.struct_fields.contains_key(&id) {
+            return llvm_ty;
+        }
+        // register the fields first since members may point to the struct itself
+        self.struct_fields.insert(id, Vec::new());
+
+        // the layout is computed in types.rs, so the LLVM struct is packed
+        // and the padding is made explicit as byte arrays
+        let st = st.borrow();
+        let mut fields = Vec::new();
+        let mut indices = Vec::new();
+        if st.is_union {
+            // members of a union are accessed by casting its address, so only the size matters
+            fields.push(LLVMArrayType(LLVMInt8Type(), st.size as u32));
+        } else {
+            let mut offset = 0;
+            for member in &st.members {
+                // bit-fields are accessed through the address of their storage units,
+                // so their bytes are left as padding
+                if member.bit_field.is_some() {
+                    indices.push(u32::MAX);
+                    continue;
+                }
+                if member.offset > offset {
+                    fields.push(LLVMArrayType(LLVMInt8Type(), (member.offset - offset) as u32));
+                }
+                indices.push(fields.len() as u32);
+                fields.push(self.type_to_llvmty(&member.ty));
+                offset = member.offset + member.ty.size();
+            }
+            if st.size > offset {
+                fields.push(LLVMArrayType(LLVMInt8Type(), (st.size - offset) as u32));
+            }
+        }
+        self.struct_fields.insert(id, indices);
+        LLVMStructSetBody(
+            llvm_ty,
+            fields.as_mut_slice().as_mut_ptr(),
+            fields.len() as u32,
+            1, // packed
+        );
+        llvm_ty
+    }
+
+    unsafe fn coerced_types(&mut self, classes: &Vec<abi::ArgClass>) -> Vec<LLVMTypeRef> {
+        let mut types = Vec::new();
+        for class in classes {
+            types.push(match class {
+                abi::ArgClass::Integer(size) => LLVMIntType((size * 8) as u32),
+                abi::ArgClass::Sse(size, _) if *size <= 4 => LLVMFloatType(),
+                abi::ArgClass::Sse(_, true) => LLVMDoubleType(),
+                abi::ArgClass::Sse(_, false) => LLVMVectorType(LLVMFloatType(), 2),
+            });
+        }
+        types
+    }
+
+    // a struct of the coerced types, used to access the eightbytes in memory
+    unsafe fn coerced_struct_ty(&mut self, types: &[LLVMTypeRef]) -> LLVMTypeRef {
+        let mut types = types.to_vec();
+        LLVMStructType(types.as_mut_slice().as_mut_ptr(), types.len() as u32, 0)
+    }
+
+    // the type of a coerced value: a single eightbyte is passed as is
+    unsafe fn coerced_ty(&mut self, types: &[LLVMTypeRef]) -> LLVMTypeRef {
+        if types.len() == 1 {
+            types[0]
+        } else {
+            self.coerced_struct_ty(types)
+        }
+    }
+
+    unsafe fn func_abi(&mut self, func_ty: &Type) -> FuncAbi {
+        let (ret_ty, param_types) = match func_ty {
+            Type::Func(ret_ty, param_types, ..) => (ret_ty, param_types),
+            _ => panic!("{} is not a function", func_ty),
+        };
+        // registers left for arguments
+        let mut int_regs = 6;
+        let mut sse_regs = 8;
+
+        let ret = if ret_ty.is_struct() && abi::is_x87(ret_ty) {
+            // returned in %st0 as a long double, though it would be passed in memory
+            ArgKind::Coerced(vec![LLVMX86FP80Type()])
+        } else if ret_ty.is_struct() {
+            match abi::classify(ret_ty) {
+                Some(classes) => ArgKind::Coerced(self.coerced_types(&classes)),
+                None => {
+                    // the hidden pointer is passed in the first register
+                    int_regs -= 1;
+                    ArgKind::Memory
+                }
+            }
+        } else {
+            ArgKind::Direct
+        };
+
+        let mut params = Vec::new();
+        for param_ty in param_types {
+            if !param_ty.is_struct() {
+                if param_ty.is_flonum() && !matches!(param_ty.strip_qual(), Type::LongDouble) {
+                    sse_regs = 0.max(sse_regs - 1);
+                } else if !matches!(param_ty.strip_qual(), Type::LongDouble) {
+                    int_regs = 0.max(int_regs - 1);
+                }
+                params.push(ArgKind::Direct);
+                continue;
+            }
+            // a struct is passed in memory if it does not fit in the registers left
+            let kind = match abi::classify(param_ty) {
+                Some(classes) => {
+                    let need_int = classes
+                        .iter()
+                        .filter(|class| matches!(class, abi::ArgClass::Integer(_)))
+                        .count() as i32;
+                    let need_sse = classes.len() as i32 - need_int;
+                    if need_int <= int_regs && need_sse <= sse_regs {
+                        int_regs -= need_int;
+                        sse_regs -= need_sse;
+                        ArgKind::Coerced(self.coerced_types(&classes))
+                    } else {
+                        ArgKind::Memory
                     }
-                    param_llvm_types.as_mut_slice().as_mut_ptr()
-                }(),
-                param_types.len() as u32,
-                0, // is variable number of args
-            ),
-            _ => panic!("Unsupported type"),
+                }
+                None => ArgKind::Memory,
+            };
+            params.push(kind);
+        }
+        FuncAbi { ret, params }
+    }
+
+    unsafe fn func_to_llvmty(&mut self, func_ty: &Type) -> LLVMTypeRef {
+        let (ret_ty, param_types, is_variadic) = match func_ty {
+            Type::Func(ret_ty, param_types, _, is_variadic) => (ret_ty, param_types, *is_variadic),
+            _ => panic!("{} is not a function", func_ty),
+        };
+        let func_abi = self.func_abi(func_ty);
+        let mut param_llvm_types: Vec<LLVMTypeRef> = Vec::new();
+        let llvm_ret_ty = match &func_abi.ret {
+            ArgKind::Direct => self.type_to_llvmty(ret_ty),
+            ArgKind::Coerced(types) => self.coerced_ty(types),
+            ArgKind::Memory => {
+                let llvm_ty = self.type_to_llvmty(ret_ty);
+                param_llvm_types.push(LLVMPointerType(llvm_ty, 0));
+                LLVMVoidType()
+            }
+        };
+        for (param_type, kind) in param_types.iter().zip(func_abi.params.iter()) {
+            let llvm_ty = self.type_to_llvmty(param_type);
+            match kind {
+                ArgKind::Direct => param_llvm_types.push(llvm_ty),
+                ArgKind::Coerced(types) => param_llvm_types.extend(types),
+                ArgKind::Memory => param_llvm_types.push(LLVMPointerType(llvm_ty, 0)),
+            }
+        }
+        LLVMFunctionType(
+            llvm_ret_ty,
+            param_llvm_types.as_mut_slice().as_mut_ptr(),
+            param_llvm_types.len() as u32,
+            is_variadic as i32,
+        )
+    }
+
+    // add sret and byval attributes to a function or a call
+    unsafe fn add_abi_attrs(&mut self, val: LLVMValueRef, func_ty: &Type, is_call: bool) {
+        let (ret_ty, param_types) = match func_ty {
+            Type::Func(ret_ty, param_types, ..) => (ret_ty, param_types),
+            _ => panic!("{} is not a function", func_ty),
+        };
+        let func_abi = self.func_abi(func_ty);
+        let mut attrs = Vec::new();
+        // attribute index of the first parameter is 1
+        let mut idx = 1;
+        if let ArgKind::Memory = func_abi.ret {
+            let llvm_ty = self.type_to_llvmty(ret_ty);
+            attrs.push((idx, self.type_attr("sret", llvm_ty)));
+            idx += 1;
+        }
+        for (param_type, kind) in param_types.iter().zip(func_abi.params.iter()) {
+            match kind {
+                // a restrict pointer parameter is the only way to access its object in the function
+                ArgKind::Direct if !is_call && param_type.qualifiers().is_restrict => {
+                    attrs.push((idx, self.enum_attr("noalias", 0)));
+                    idx += 1;
+                }
+                ArgKind::Direct => idx += 1,
+                ArgKind::Coerced(types) => idx += types.len() as u32,
+                ArgKind::Memory => {
+                    let llvm_ty = self.type_to_llvmty(param_type);
+                    attrs.push((idx, self.type_attr("byval", llvm_ty)));
+                    let align = param_type.align().max(8) as u64;
+                    attrs.push((idx, self.enum_attr("align", align)));
+                    idx += 1;
+                }
+            }
+        }
+        for (idx, attr) in attrs {
+            if is_call {
+                LLVMAddCallSiteAttribute(val, idx, attr);
+            } else {
+                LLVMAddAttributeAtIndex(val, idx, attr);
+            }
         }
     }
 
+    unsafe fn type_attr(&mut self, name: &str, ty: LLVMTypeRef) -> LLVMAttributeRef {
+        let kind = LLVMGetEnumAttributeKindForName(name.as_ptr() as *const _, name.len());
+        LLVMCreateTypeAttribute(self.context, kind, ty)
+    }
+
+    unsafe fn enum_attr(&mut self, name: &str, val: u64) -> LLVMAttributeRef {
+        let kind = LLVMGetEnumAttributeKindForName(name.as_ptr() as *const _, name.len());
+        LLVMCreateEnumAttribute(self.context, kind, val)
+    }
+
     pub unsafe fn dump_module(&self) {
         LLVMDumpModule(self.module);
     }
@@ -111,11 +422,37 @@ impl Codegen {
     }
 
     pub unsafe fn gen_program(&mut self, program: Vec<AST>) {
+        // declare all functions and global variables first so that they can be referred to
+        // before their definitions; a definition of a function takes precedence over its declarations
+        for top_level in &program {
+            if let AST::FuncDef(func_ty, func_name, _, attr) = top_level {
+                self.declare_func(func_ty, func_name);
+                if attr.is_static {
+                    let func = self.global_varmap.get(func_name).unwrap().llvm_val;
+                    LLVMSetLinkage(func, llvm::LLVMLinkage::LLVMInternalLinkage);
+                }
+            }
+        }
+        for top_level in &program {
+            match top_level {
+                AST::FuncDecl(func_ty, func_name, _) if !self.global_varmap.contains_key(func_name) => {
+                    self.declare_func(func_ty, func_name);
+                }
+                AST::VariableDecl(ty, name, _, attr) => {
+                    self.declare_global_var(ty, name, attr);
+                }
+                _ => {}
+            }
+        }
         for top_level in program {
             match top_level {
-                AST::FuncDef(func_ty, func_name, body) => {
-                    self.gen_func_def(func_ty, func_name, body);
+                AST::FuncDef(func_ty, func_name, body, _) => {
+                    self.gen_func_def(*func_ty, func_name, *body);
                 }
+                AST::VariableDecl(_, name, init_opt, attr) => {
+                    self.gen_global_var_init(&name, &init_opt, &attr);
+                }
+                AST::FuncDecl(..) => {}
                 _ => panic!("Unsupported node type"),
             }
         }
@@ -144,23 +481,332 @@ impl Codegen {
         */
     }
 
-    pub unsafe fn gen_func_def(&mut self, func_ty: Box<Type>, func_name: String, body: Box<AST>) {
-        let func_ty = self.type_to_llvmty(&func_ty);
-        let func = LLVMAddFunction(self.module, CString::new(func_name.as_str()).unwrap().as_ptr(), func_ty);
+    unsafe fn declare_func(&mut self, func_ty: &Type, func_name: &str) {
+        let llvm_func_ty = self.type_to_llvmty(func_ty);
+        let func = LLVMAddFunction(
+            self.module,
+            CString::new(func_name).unwrap().as_ptr(),
+            llvm_func_ty,
+        );
+        self.add_abi_attrs(func, func_ty, false);
+        self.global_varmap
+            .insert(func_name.to_string(), VarInfo::new(func_ty.clone(), func));
+    }
+
+    unsafe fn declare_global_var(&mut self, ty: &Type, name: &str, attr: &VarAttr) -> LLVMValueRef {
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = LLVMAddGlobal(self.module, llvm_ty, CString::new(name).unwrap().as_ptr());
+        LLVMSetAlignment(var, ty.align().max(attr.align.unwrap_or(0)) as u32);
+        LLVMSetThreadLocal(var, attr.is_thread_local as i32);
+        self.global_varmap
+            .insert(name.to_string(), VarInfo::new(ty.clone(), var));
+        var
+    }
+
+    unsafe fn gen_global_var_init(&mut self, name: &String, init_opt: &Option<Box<AST>>, attr: &VarAttr) {
+        // an extern declaration refers to an object defined in another translation unit
+        if attr.is_extern && init_opt.is_none() {
+            return;
+        }
+        let var_info = self.global_varmap.get(name).unwrap();
+        let (var, ty) = (var_info.llvm_val, var_info.ty.clone());
+        if attr.is_static {
+            LLVMSetLinkage(var, llvm::LLVMLinkage::LLVMInternalLinkage);
+        }
+        let var = self.gen_static_init(var, &ty, name, init_opt);
+        self.global_varmap.get_mut(name).unwrap().llvm_val = var;
+    }
+
+    // initializers of global and static variables are folded into constants by the builder,
+    // since sema only allows constant operands; returns the variable, which is replaced if
+    // the initializer has another LLVM type
+    unsafe fn gen_static_init(
+        &mut self,
+        var: LLVMValueRef,
+        ty: &Type,
+        name: &str,
+        init_opt: &Option<Box<AST>>,
+    ) -> LLVMValueRef {
+        let init = match init_opt.as_deref() {
+            Some(AST::Initializer(leaves)) => {
+                let mut vals = Vec::new();
+                for leaf in leaves {
+                    vals.push(self.gen_init_leaf(leaf));
+                }
+                if vals.iter().flatten().any(|&val| LLVMIsConstant(val) == 0) {
+                    panic!("Initializer element of {} is not a compile-time constant", name);
+                }
+                self.gen_const_image(ty, leaves, &vals)
+            }
+            Some(init) => {
+                let val = self.gen(init).unwrap().0;
+                if LLVMIsConstant(val) == 0 {
+                    panic!("Initializer element of {} is not a compile-time constant", name);
+                }
+                val
+            }
+            // a tentative definition is initialized with zero
+            None => LLVMConstNull(LLVMGetElementType(LLVMTypeOf(var))),
+        };
+        self.set_global_initializer(var, init)
+    }
+
+    // the value of a leaf of an initializer, or None for a string literal, whose bytes are copied
+    unsafe fn gen_init_leaf(&mut self, leaf: &InitLeaf) -> Option<LLVMValueRef> {
+        match &leaf.expr {
+            AST::Str(_) => None,
+            expr => Some(self.gen(expr).unwrap().0),
+        }
+    }
+
+    // the bytes of an object initialized with constants: a packed struct of the values of the
+    // leaves at their offsets, and byte arrays between them which hold strings, bit-fields and zeros
+    unsafe fn gen_const_image(&mut self, ty: &Type, leaves: &[InitLeaf], vals: &[Option<LLVMValueRef>]) -> LLVMValueRef {
+        let mut bytes = vec![0u8; ty.size() as usize];
+        let mut fields = Vec::new();
+        for (leaf, val) in leaves.iter().zip(vals.iter()) {
+            let offset = leaf.offset as usize;
+            match (&leaf.expr, leaf.bit_field, val) {
+                (AST::Str(s), _, _) => bytes[offset..offset + s.len()].copy_from_slice(s),
+                (_, Some(bit_field), Some(val)) => {
+                    if LLVMIsAConstantInt(*val).is_null() {
+                        panic!("Initializer of a bit-field is not an integer constant");
+                    }
+                    let unit = &mut bytes[offset..offset + leaf.ty.size() as usize];
+                    let mask = if bit_field.width == 64 {
+                        u64::MAX
+                    } else {
+                        (1u64 << bit_field.width) - 1
+                    };
+                    let mut bits = unit.iter().rev().fold(0u64, |bits, &b| bits << 8 | b as u64);
+                    bits &= !(mask << bit_field.offset);
+                    bits |= (LLVMConstIntGetZExtValue(*val) & mask) << bit_field.offset;
+                    for (i, b) in unit.iter_mut().enumerate() {
+                        *b = (bits >> (i * 8)) as u8;
+                    }
+                }
+                (_, None, Some(val)) => fields.push((offset, leaf.ty.size() as usize, *val)),
+                _ => unreachable!(),
+            }
+        }
+        fields.sort_by_key(|(offset, _, _)| *offset);
+        let mut vals = Vec::new();
+        let mut cur = 0;
+        for (offset, size, val) in fields {
+            if offset > cur {
+                vals.push(self.const_bytes(&bytes[cur..offset]));
+            }
+            vals.push(val);
+            cur = offset + size;
+        }
+        if bytes.len() > cur {
+            vals.push(self.const_bytes(&bytes[cur..]));
+        }
+        LLVMConstStructInContext(self.context, vals.as_mut_ptr(), vals.len() as u32, 1)
+    }
+
+    unsafe fn const_bytes(&mut self, bytes: &[u8]) -> LLVMValueRef {
+        if bytes.iter().all(|&b| b == 0) {
+            return LLVMConstNull(LLVMArrayType(LLVMInt8Type(), bytes.len() as u32));
+        }
+        LLVMConstStringInContext(self.context, bytes.as_ptr() as *const _, bytes.len() as u32, 1)
+    }
+
+    // a global variable whose initializer has another LLVM type than the variable (e.g. one with
+    // bit-fields or a union) is replaced by a global of the type of the initializer, and the
+    // returned value is the new global cast to the type of the old one
+    unsafe fn set_global_initializer(&mut self, var: LLVMValueRef, init: LLVMValueRef) -> LLVMValueRef {
+        let var_ty = LLVMTypeOf(var);
+        if LLVMGetElementType(var_ty) == LLVMTypeOf(init) {
+            LLVMSetInitializer(var, init);
+            return var;
+        }
+        let mut len = 0;
+        let name = CStr::from_ptr(LLVMGetValueName2(var, &mut len)).to_owned();
+        let new_var = LLVMAddGlobal(self.module, LLVMTypeOf(init), cstr("").as_ptr());
+        LLVMSetInitializer(new_var, init);
+        LLVMSetAlignment(new_var, LLVMGetAlignment(var));
+        LLVMSetThreadLocal(new_var, LLVMIsThreadLocal(var));
+        LLVMSetLinkage(new_var, LLVMGetLinkage(var));
+        LLVMSetGlobalConstant(new_var, LLVMIsGlobalConstant(var));
+        LLVMSetUnnamedAddress(new_var, LLVMGetUnnamedAddress(var));
+        let cast = LLVMConstBitCast(new_var, var_ty);
+        LLVMReplaceAllUsesWith(var, cast);
+        LLVMDeleteGlobal(var);
+        LLVMSetValueName2(new_var, name.as_ptr(), len);
+        cast
+    }
+
+    // initialize a local object; a constant aggregate larger than two eightbytes is copied
+    // from a constant global, and others are zero-filled unless their leaves cover them
+    unsafe fn gen_local_init(&mut self, var: LLVMValueRef, ty: &Type, init: &AST, name: &str) {
+        let leaves = match init {
+            AST::Initializer(leaves) => leaves,
+            init => {
+                let val = self.gen(init).unwrap().0;
+                let store = LLVMBuildStore(self.builder, val, var);
+                self.set_access_qualifiers(store, ty);
+                return;
+            }
+        };
+        let mut vals = Vec::new();
+        for leaf in leaves {
+            vals.push(self.gen_init_leaf(leaf));
+        }
+        let size = LLVMConstInt(LLVMInt64Type(), ty.size() as u64, 0);
+        let align = ty.align() as u32;
+        let bytes = LLVMBuildPointerCast(self.builder, var, LLVMPointerType(LLVMInt8Type(), 0), cstr("bytes").as_ptr());
+        if ty.size() > 16 && vals.iter().flatten().all(|&val| LLVMIsConstant(val) != 0) {
+            let image = self.gen_const_image(ty, leaves, &vals);
+            let func_name = CStr::from_ptr(LLVMGetValueName2(self.cur_func.unwrap(), &mut 0));
+            let global_name = format!("__const.{}.{}", func_name.to_str().unwrap(), name);
+            let global = LLVMAddGlobal(self.module, LLVMTypeOf(image), CString::new(global_name).unwrap().as_ptr());
+            LLVMSetInitializer(global, image);
+            LLVMSetLinkage(global, llvm::LLVMLinkage::LLVMPrivateLinkage);
+            LLVMSetGlobalConstant(global, 1);
+            LLVMSetUnnamedAddress(global, llvm::LLVMUnnamedAddr::LLVMGlobalUnnamedAddr);
+            LLVMSetAlignment(global, align);
+            let src = LLVMConstBitCast(global, LLVMPointerType(LLVMInt8Type(), 0));
+            LLVMBuildMemCpy(self.builder, bytes, align, src, align, size);
+            return;
+        }
+        // leaves do not overlap, so they cover the object if their sizes add up to it
+        let covered: i32 = leaves
+            .iter()
+            .map(|leaf| match (&leaf.expr, leaf.bit_field) {
+                (AST::Str(s), _) => (s.len() as i32 + 1).min(leaf.ty.size()),
+                (_, Some(_)) => 0,
+                _ => leaf.ty.size(),
+            })
+            .sum();
+        let is_zero_filled = covered < ty.size();
+        if is_zero_filled {
+            LLVMBuildMemSet(self.builder, bytes, LLVMConstInt(LLVMInt8Type(), 0, 0), size, align);
+        }
+        for (leaf, val) in leaves.iter().zip(vals) {
+            let mut idx = [LLVMConstInt(LLVMInt64Type(), leaf.offset as u64, 0)];
+            let addr = LLVMBuildGEP(self.builder, bytes, idx.as_mut_ptr(), 1, cstr("init").as_ptr());
+            match (&leaf.expr, leaf.bit_field, val) {
+                (AST::Str(s), _, _) => {
+                    let src = self.gen_str(s).unwrap().0;
+                    let src = LLVMConstBitCast(src, LLVMPointerType(LLVMInt8Type(), 0));
+                    let len = (s.len() as i32 + 1).min(leaf.ty.size());
+                    LLVMBuildMemCpy(self.builder, addr, 1, src, 1, LLVMConstInt(LLVMInt64Type(), len as u64, 0));
+                }
+                (_, _, Some(val)) if is_zero_filled && LLVMIsConstant(val) != 0 && LLVMIsNull(val) != 0 => (),
+                (_, bit_field, Some(val)) => {
+                    let llvm_ty = self.type_to_llvmty(&leaf.ty);
+                    let ptr = LLVMBuildPointerCast(self.builder, addr, LLVMPointerType(llvm_ty, 0), cstr("init").as_ptr());
+                    match bit_field {
+                        Some(bit_field) => self.gen_bit_field_store(val, ptr, &leaf.ty, bit_field),
+                        None => {
+                            let store = LLVMBuildStore(self.builder, val, ptr);
+                            self.set_access_qualifiers(store, &leaf.ty);
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // an unnamed object, which has static storage at file scope (in an initializer of a global
+    // variable) and automatic storage in a function
+    unsafe fn gen_compound_literal(&mut self, ty: &Type, init: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = match self.cur_func {
+            None => {
+                let var = LLVMAddGlobal(self.module, llvm_ty, cstr(".compoundliteral").as_ptr());
+                LLVMSetLinkage(var, llvm::LLVMLinkage::LLVMInternalLinkage);
+                LLVMSetAlignment(var, ty.align() as u32);
+                self.gen_static_init(var, ty, "compound literal", &Some(Box::new(init.clone())))
+            }
+            Some(_) => {
+                let var = self.build_entry_alloca(ty, "compoundliteral");
+                self.gen_local_init(var, ty, init, "compoundliteral");
+                var
+            }
+        };
+        Some((var, Some(Type::Ptr(Box::new(ty.clone())))))
+    }
+
+    pub unsafe fn gen_func_def(&mut self, func_ty: Type, func_name: String, body: AST) {
+        let func_abi = self.func_abi(&func_ty);
+        let (param_types, param_names) = match func_ty {
+            Type::Func(_, param_types, param_names, _) => (param_types, param_names),
+            _ => panic!("{} is not a function", func_name),
+        };
+        let func = self.global_varmap.get(&func_name).unwrap().llvm_val;
         let bb_entry = LLVMAppendBasicBlock(func, cstr("entry").as_ptr());
         LLVMPositionBuilderAtEnd(self.builder, bb_entry);
 
         self.cur_func = Some(func);
 
         self.local_varmap.push(HashMap::new());
-        // TODO: register arguments as local variables
+        // register arguments as local variables
+        let mut idx = 0;
+        self.cur_sret = None;
+        if let ArgKind::Memory = func_abi.ret {
+            self.cur_sret = Some(LLVMGetParam(func, 0));
+            idx += 1;
+        }
+        for ((ty, name), kind) in param_types
+            .iter()
+            .zip(param_names.iter())
+            .zip(func_abi.params.iter())
+        {
+            match kind {
+                ArgKind::Direct => {
+                    self.gen_local_var_decl(ty, name, &None, &VarAttr::default());
+                    let var = self.local_varmap.last().unwrap().get(name).unwrap().llvm_val;
+                    LLVMBuildStore(self.builder, LLVMGetParam(func, idx), var);
+                    idx += 1;
+                }
+                ArgKind::Coerced(types) => {
+                    self.gen_local_var_decl(ty, name, &None, &VarAttr::default());
+                    let var = self.local_varmap.last().unwrap().get(name).unwrap().llvm_val;
+                    LLVMSetAlignment(var, ty.align().max(8) as u32);
+                    let coerced_ty = self.coerced_struct_ty(types);
+                    let ptr = LLVMBuildPointerCast(
+                        self.builder,
+                        var,
+                        LLVMPointerType(coerced_ty, 0),
+                        cstr("coerce").as_ptr(),
+                    );
+                    for i in 0..types.len() {
+                        let field =
+                            LLVMBuildStructGEP(self.builder, ptr, i as u32, cstr("coerce").as_ptr());
+                        LLVMBuildStore(self.builder, LLVMGetParam(func, idx), field);
+                        idx += 1;
+                    }
+                }
+                ArgKind::Memory => {
+                    // the caller passes a copy (byval), which is used as the variable itself
+                    self.local_varmap
+                        .last_mut()
+                        .unwrap()
+                        .insert(name.clone(), VarInfo::new(ty.clone(), LLVMGetParam(func, idx)));
+                    idx += 1;
+                }
+            }
+        }
+        self.cur_func_abi = Some(func_abi);
 
-        self.gen(&*body);
+        self.gen(&body);
         if !is_exist_terminator(self.builder) {
-            LLVMBuildRet(self.builder, self.make_int(0, false).unwrap().0);
+            let ret_ty = LLVMGetReturnType(LLVMGetElementType(LLVMTypeOf(func)));
+            if LLVMGetTypeKind(ret_ty) == llvm::LLVMTypeKind::LLVMVoidTypeKind {
+                LLVMBuildRetVoid(self.builder);
+            } else if func_name == "main" {
+                LLVMBuildRet(self.builder, LLVMConstNull(ret_ty));
+            } else {
+                // sema warns that the end is reachable; the value must not be used by the caller
+                LLVMBuildRet(self.builder, LLVMGetUndef(ret_ty));
+            }
         }
         //println!("{:?}", self.local_varmap.last_mut().unwrap());
         self.local_varmap.pop();
+        self.cur_func = None;
     }
 
     pub unsafe fn gen(&mut self, ast: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
@@ -168,26 +814,82 @@ impl Codegen {
             AST::Block(ref block) => self.gen_block(block),
             AST::UnaryOp(ref ast, ref op) => self.gen_unary_op(&**ast, &*op),
             AST::BinaryOp(ref lhs, ref rhs, ref op) => self.gen_binary_op(&**lhs, &**rhs, &*op),
-            AST::Int(ref n) => self.make_int(*n as u64, false),
+            AST::Int(ref n, ref ty) => self.make_int(*n as u64, ty),
+            AST::Float(ref f, ref ty) => self.make_float(*f, ty),
             AST::If(ref cond, ref then, ref els) => self.gen_if(&**cond, &**then, &**els),
             AST::For(ref init, ref cond, ref step, ref body) => self.gen_for(&**init, &**cond, &**step, &**body),
+            AST::While(ref cond, ref body) => self.gen_while(cond, body),
             AST::Return(None) => Some((LLVMBuildRetVoid(self.builder), None)),
             AST::Return(Some(ref val)) => self.gen_return(val),
             AST::Load(ref expr) => self.gen_load(expr),
+            AST::Cast(ref expr, ref ty) => self.gen_cast(expr, ty),
             AST::Variable(ref name) => self.gen_var(name),
-            AST::VariableDecl(ref ty, ref name, ref init_opt) => {
-                self.gen_local_var_decl(ty, name, init_opt)
+            AST::Str(ref s) => self.gen_str(s),
+            AST::Member(ref expr, ref name) => self.gen_member(expr, name),
+            AST::Index(ref ptr, ref idx) => self.gen_index(ptr, idx),
+            AST::CompoundLiteral(ref ty, ref init) => self.gen_compound_literal(ty, init),
+            AST::VariableDecl(ref ty, ref name, ref init_opt, ref attr) => {
+                self.gen_local_var_decl(ty, name, init_opt, attr)
             }
+            AST::FuncDecl(ref func_ty, ref func_name, _) => {
+                if !self.global_varmap.contains_key(func_name) {
+                    self.declare_func(func_ty, func_name);
+                }
+                None
+            }
+            AST::FuncCall(ref callee, ref args) => self.gen_func_call(callee, args),
+            AST::VaStart(ref ap) => self.gen_va_intrinsic("llvm.va_start", &[ap]),
+            AST::VaArg(ref ap, ref ty) => self.gen_va_arg(ap, ty),
+            AST::VaEnd(ref ap) => self.gen_va_intrinsic("llvm.va_end", &[ap]),
+            AST::VaCopy(ref dest, ref src) => self.gen_va_intrinsic("llvm.va_copy", &[dest, src]),
             _ => None,
         }
     }
 
     pub unsafe fn gen_block(&mut self, block: &Vec<AST>) -> Option<(LLVMValueRef, Option<Type>)> {
-        // TODO: support scope
+        self.local_varmap.push(HashMap::new());
+        // variable length arrays are freed on leaving the block by restoring the stack pointer
+        let has_vla = block
+            .iter()
+            .any(|ast| matches!(ast, AST::VariableDecl(ty, _, _, attr) if ty.is_vla() && !attr.is_extern && !attr.is_static));
+        let i8_ptr_ty = LLVMPointerType(LLVMInt8Type(), 0);
+        let stack = if has_vla {
+            let func = self.get_intrinsic("llvm.stacksave", i8_ptr_ty, &mut []);
+            Some(LLVMBuildCall(self.builder, func, [].as_mut_ptr(), 0, cstr("stack").as_ptr()))
+        } else {
+            None
+        };
+        self.gen_block_items(block);
+        if let Some(mut stack) = stack {
+            if !is_exist_terminator(self.builder) {
+                let func = self.get_intrinsic("llvm.stackrestore", LLVMVoidType(), &mut [i8_ptr_ty]);
+                LLVMBuildCall(self.builder, func, &mut stack, 1, cstr("").as_ptr());
+            }
+        }
+        self.local_varmap.pop();
+        None
+    }
+
+    // declaration of an LLVM intrinsic, added to the module on first use
+    unsafe fn get_intrinsic(&mut self, name: &'static str, ret_ty: LLVMTypeRef, param_types: &mut [LLVMTypeRef]) -> LLVMValueRef {
+        let func = LLVMGetNamedFunction(self.module, cstr(name).as_ptr());
+        if !func.is_null() {
+            return func;
+        }
+        let func_ty = LLVMFunctionType(ret_ty, param_types.as_mut_ptr(), param_types.len() as u32, 0);
+        LLVMAddFunction(self.module, cstr(name).as_ptr(), func_ty)
+    }
+
+    // the items of a block, in the current scope
+    unsafe fn gen_block_items(&mut self, block: &Vec<AST>) {
         for ast in block {
+            // statements after return are emitted into a block without predecessors
+            if is_exist_terminator(self.builder) {
+                let bb_unreachable = LLVMAppendBasicBlock(self.cur_func.unwrap(), cstr("unreachable").as_ptr());
+                LLVMPositionBuilderAtEnd(self.builder, bb_unreachable);
+            }
             self.gen(ast);
         }
-        None
     }
 
     pub unsafe fn gen_local_var_decl(
@@ -195,19 +897,24 @@ impl Codegen {
         ty: &Type,
         name: &String,
         init_opt: &Option<Box<AST>>,
+        attr: &VarAttr,
     ) -> Option<(LLVMValueRef, Option<Type>)> {
-        let func = self.cur_func.unwrap();
-        let builder = LLVMCreateBuilderInContext(self.context);
-        let entry_bb = LLVMGetEntryBasicBlock(func);
-        // insert declaration at first position of the function
-        let first_inst = LLVMGetFirstInstruction(entry_bb);
-        if first_inst == ptr::null_mut() {
-            LLVMPositionBuilderAtEnd(builder, entry_bb);
+        if attr.is_extern {
+            self.gen_local_extern_decl(ty, name, attr);
+            return None;
+        }
+        if attr.is_static {
+            self.gen_local_static_decl(ty, name, init_opt, attr);
+            return None;
+        }
+        let var = if ty.is_vla() {
+            self.build_vla_alloca(ty, name)
         } else {
-            LLVMPositionBuilderBefore(builder, first_inst);
+            self.build_entry_alloca(ty, name)
+        };
+        if let Some(align) = attr.align {
+            LLVMSetAlignment(var, ty.align().max(align) as u32);
         }
-        let llvm_ty = self.type_to_llvmty(ty);
-        let var = LLVMBuildAlloca(builder, llvm_ty, CString::new(name.as_str()).unwrap().as_ptr());
 
         self.local_varmap
             .last_mut()
@@ -219,29 +926,267 @@ impl Codegen {
 
         // initialize variables
         if let Some(init) = init_opt {
-            self.gen_assign(&AST::Variable(name.clone()), &init);
+            self.gen_local_init(var, ty, init, name);
         }
 
         None
     }
 
+    // an extern declaration in a function refers to the global variable
+    unsafe fn gen_local_extern_decl(&mut self, ty: &Type, name: &String, attr: &VarAttr) {
+        let global = match self.global_varmap.get(name) {
+            Some(var_info) => var_info.llvm_val,
+            None => self.declare_global_var(ty, name, attr),
+        };
+        // the global variable may have been declared with a different but compatible type
+        // (e.g. int a[] and int a[3])
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = LLVMConstBitCast(global, LLVMPointerType(llvm_ty, 0));
+        self.local_varmap
+            .last_mut()
+            .unwrap()
+            .insert(name.clone(), VarInfo::new(ty.clone(), var));
+    }
+
+    // a static local variable is a global variable with internal linkage, named after
+    // the function (e.g. main.count) to avoid clashes
+    unsafe fn gen_local_static_decl(&mut self, ty: &Type, name: &String, init_opt: &Option<Box<AST>>, attr: &VarAttr) {
+        let mut len = 0;
+        let func_name = CStr::from_ptr(LLVMGetValueName2(self.cur_func.unwrap(), &mut len));
+        let global_name = format!("{}.{}", func_name.to_str().unwrap(), name);
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = LLVMAddGlobal(self.module, llvm_ty, CString::new(global_name).unwrap().as_ptr());
+        LLVMSetAlignment(var, ty.align().max(attr.align.unwrap_or(0)) as u32);
+        LLVMSetThreadLocal(var, attr.is_thread_local as i32);
+        LLVMSetLinkage(var, llvm::LLVMLinkage::LLVMInternalLinkage);
+        self.local_varmap
+            .last_mut()
+            .unwrap()
+            .insert(name.clone(), VarInfo::new(ty.clone(), var));
+        let var = self.gen_static_init(var, ty, name, init_opt);
+        self.local_varmap.last_mut().unwrap().get_mut(name).unwrap().llvm_val = var;
+    }
+
+    unsafe fn build_entry_alloca(&mut self, ty: &Type, name: &str) -> LLVMValueRef {
+        let func = self.cur_func.unwrap();
+        let builder = LLVMCreateBuilderInContext(self.context);
+        let entry_bb = LLVMGetEntryBasicBlock(func);
+        // insert declaration at first position of the function
+        let first_inst = LLVMGetFirstInstruction(entry_bb);
+        if first_inst == ptr::null_mut() {
+            LLVMPositionBuilderAtEnd(builder, entry_bb);
+        } else {
+            LLVMPositionBuilderBefore(builder, first_inst);
+        }
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = LLVMBuildAlloca(builder, llvm_ty, CString::new(name).unwrap().as_ptr());
+        LLVMSetAlignment(var, ty.align() as u32);
+        LLVMDisposeBuilder(builder);
+        var
+    }
+
+    // a variable length array is allocated where it is declared, since its length is known
+    // only there; the allocation is freed by the enclosing block
+    unsafe fn build_vla_alloca(&mut self, ty: &Type, name: &str) -> LLVMValueRef {
+        let count = self.gen_vla_count(ty);
+        let llvm_ty = self.type_to_llvmty(ty);
+        let var = LLVMBuildArrayAlloca(self.builder, llvm_ty, count, CString::new(name).unwrap().as_ptr());
+        LLVMSetAlignment(var, ty.align() as u32);
+        var
+    }
+
+    // number of the innermost elements in a variable length array, as i64
+    unsafe fn gen_vla_count(&mut self, ty: &Type) -> LLVMValueRef {
+        match ty {
+            Type::Vla(elem_ty, name) => {
+                let len_ptr = self.gen_var(name).unwrap().0;
+                let len = LLVMBuildLoad(self.builder, len_ptr, cstr("vla.len").as_ptr());
+                if elem_ty.is_vla() {
+                    let elem_count = self.gen_vla_count(elem_ty);
+                    LLVMBuildMul(self.builder, len, elem_count, cstr("vla.count").as_ptr())
+                } else {
+                    len
+                }
+            }
+            _ => panic!("{} is not a variable length array", ty),
+        }
+    }
+
+    // an index into elements of type ty, scaled to the innermost elements if it is a
+    // variable length array
+    unsafe fn scale_vla_index(&mut self, idx_val: LLVMValueRef, ty: &Type) -> LLVMValueRef {
+        if !ty.is_vla() {
+            return idx_val;
+        }
+        let count = self.gen_vla_count(ty);
+        LLVMBuildMul(self.builder, idx_val, count, cstr("vla.idx").as_ptr())
+    }
+
+    // store a value into a temporary variable and return its address;
+    // the temporary is aligned enough to be accessed by eightbytes
+    unsafe fn spill(&mut self, val: LLVMValueRef, ty: &Type) -> LLVMValueRef {
+        let tmp = self.build_entry_alloca(ty, "tmp");
+        LLVMSetAlignment(tmp, ty.align().max(16) as u32);
+        LLVMBuildStore(self.builder, val, tmp);
+        tmp
+    }
+
+    // address of an element, as a GEP from the pointer
+    unsafe fn gen_index(&mut self, ptr: &AST, idx: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
+        let (ptr_val, ptr_ty) = self.gen(ptr).unwrap();
+        let idx_val = self.gen(idx).unwrap().0;
+        let idx_val = match &ptr_ty {
+            Some(Type::Ptr(elem_ty)) => self.scale_vla_index(idx_val, elem_ty),
+            _ => idx_val,
+        };
+        let mut indices = [idx_val];
+        let elem = LLVMBuildGEP(
+            self.builder,
+            ptr_val,
+            indices.as_mut_ptr(),
+            1,
+            cstr("elem").as_ptr(),
+        );
+        Some((elem, ptr_ty))
+    }
+
+    unsafe fn gen_member(&mut self, ast: &AST, name: &str) -> Option<(LLVMValueRef, Option<Type>)> {
+        let (ptr, ty, _) = self.gen_member_ref(ast, name);
+        Some((ptr, Some(Type::Ptr(Box::new(ty)))))
+    }
+
+    // returns the address of the member (the storage unit for a bit-field) and its type
+    unsafe fn gen_member_ref(&mut self, ast: &AST, name: &str) -> (LLVMValueRef, Type, Option<BitField>) {
+        let (val, ty) = self.gen(ast).unwrap();
+        let (mut ptr, mut ty) = match ty.unwrap() {
+            // address of the struct
+            Type::Ptr(ty) => (val, *ty),
+            // a struct which is not an lvalue (e.g. returned from a function)
+            ty => (self.spill(val, &ty), ty),
+        };
+        let path = match ty.strip_qual() {
+            Type::Struct(st) => st.borrow().find_member(name).unwrap(),
+            _ => panic!("{} is not a struct", ty),
+        };
+        // members of a volatile struct are accessed as volatile
+        let quals = Qualifiers {
+            is_const: ty.is_const(),
+            is_volatile: ty.qualifiers().is_volatile,
+            ..Default::default()
+        };
+        let mut bit_field = None;
+        for i in path {
+            let st = match ty.strip_qual() {
+                Type::Struct(st) => st.clone(),
+                _ => unreachable!(),
+            };
+            self.type_to_llvmty(&ty);
+            let st = st.borrow();
+            let member = &st.members[i];
+            let member_llvm_ty = self.type_to_llvmty(&member.ty);
+            bit_field = member.bit_field;
+            ptr = if member.bit_field.is_some() {
+                let bytes = LLVMBuildPointerCast(
+                    self.builder,
+                    ptr,
+                    LLVMPointerType(LLVMInt8Type(), 0),
+                    cstr("bytes").as_ptr(),
+                );
+                let mut idx = [LLVMConstInt(LLVMInt64Type(), member.offset as u64, 0)];
+                let unit = LLVMBuildGEP(self.builder, bytes, idx.as_mut_ptr(), 1, cstr("unit").as_ptr());
+                LLVMBuildPointerCast(
+                    self.builder,
+                    unit,
+                    LLVMPointerType(member_llvm_ty, 0),
+                    cstr("member").as_ptr(),
+                )
+            } else if st.is_union {
+                LLVMBuildPointerCast(
+                    self.builder,
+                    ptr,
+                    LLVMPointerType(member_llvm_ty, 0),
+                    cstr("member").as_ptr(),
+                )
+            } else {
+                let field_idx = self.struct_fields.get(&st.id).unwrap()[i];
+                LLVMBuildStructGEP(self.builder, ptr, field_idx, cstr("member").as_ptr())
+            };
+            ty = member.ty.clone().qualified(quals);
+        }
+        (ptr, ty, bit_field)
+    }
+
+    // a volatile access is never removed or reordered, and an atomic one is sequentially
+    // consistent; LLVM has no atomic access of aggregates or x86_fp80, which are left plain
+    unsafe fn set_access_qualifiers(&mut self, inst: LLVMValueRef, ty: &Type) {
+        let quals = ty.qualifiers();
+        if quals.is_volatile {
+            LLVMSetVolatile(inst, 1);
+        }
+        if quals.is_atomic && ty.is_scalar() && !matches!(ty.strip_qual(), Type::LongDouble) {
+            LLVMSetOrdering(inst, llvm::LLVMAtomicOrdering::LLVMAtomicOrderingSequentiallyConsistent);
+        }
+    }
+
+    // extract a bit-field from its storage unit, sign-extending it if its type is signed
+    unsafe fn gen_bit_field_load(&mut self, unit: LLVMValueRef, ty: &Type, bit_field: BitField) -> LLVMValueRef {
+        let unit_bits = ty.size() * 8;
+        let llvm_ty = self.type_to_llvmty(ty);
+        let val = LLVMBuildLoad(self.builder, unit, cstr("unit").as_ptr());
+        self.set_access_qualifiers(val, ty);
+        // move the field to the top bits, then shift it back to the bottom
+        let shl = LLVMConstInt(llvm_ty, (unit_bits - bit_field.offset - bit_field.width) as u64, 0);
+        let val = LLVMBuildShl(self.builder, val, shl, cstr("bitfield").as_ptr());
+        let shr = LLVMConstInt(llvm_ty, (unit_bits - bit_field.width) as u64, 0);
+        if ty.is_unsigned() {
+            LLVMBuildLShr(self.builder, val, shr, cstr("bitfield").as_ptr())
+        } else {
+            LLVMBuildAShr(self.builder, val, shr, cstr("bitfield").as_ptr())
+        }
+    }
+
+    // replace the bits of a bit-field in its storage unit with the value
+    unsafe fn gen_bit_field_store(&mut self, val: LLVMValueRef, unit: LLVMValueRef, ty: &Type, bit_field: BitField) {
+        let llvm_ty = self.type_to_llvmty(ty);
+        let mask = if bit_field.width == 64 {
+            u64::MAX
+        } else {
+            (1u64 << bit_field.width) - 1
+        };
+        let offset = LLVMConstInt(llvm_ty, bit_field.offset as u64, 0);
+        let val = LLVMBuildAnd(self.builder, val, LLVMConstInt(llvm_ty, mask, 0), cstr("bitfield").as_ptr());
+        let val = LLVMBuildShl(self.builder, val, offset, cstr("bitfield").as_ptr());
+        let old = LLVMBuildLoad(self.builder, unit, cstr("unit").as_ptr());
+        self.set_access_qualifiers(old, ty);
+        let keep = LLVMConstInt(llvm_ty, !(mask << bit_field.offset), 0);
+        let old = LLVMBuildAnd(self.builder, old, keep, cstr("unit").as_ptr());
+        let new = LLVMBuildOr(self.builder, old, val, cstr("unit").as_ptr());
+        let store = LLVMBuildStore(self.builder, new, unit);
+        self.set_access_qualifiers(store, ty);
+    }
+
     pub unsafe fn gen_unary_op(
         &mut self,
         ast: &AST,
         op: &UnaryOps,
     ) -> Option<(LLVMValueRef, Option<Type>)> {
-        let res = match op {
+        match op {
             UnaryOps::Plus => self.gen(ast),
             UnaryOps::Minus => {
-                let val = self.gen(ast).unwrap().0;
-                let neg = LLVMBuildNeg(self.builder, val, cstr("neg").as_ptr());
-                Some((neg, Some(Type::Int)))
+                let (val, ty) = self.gen(ast).unwrap();
+                let ty = ty.unwrap();
+                let neg = if ty.is_flonum() {
+                    LLVMBuildFNeg(self.builder, val, cstr("fneg").as_ptr())
+                } else {
+                    LLVMBuildNeg(self.builder, val, cstr("neg").as_ptr())
+                };
+                Some((neg, Some(ty)))
             }
-            UnaryOps::Addr => self.gen(inside_load(ast)),
-            UnaryOps::Deref => self.gen_load(ast),
-            _ => panic!("Unsupported unary op"),
-        };
-        res
+            // the address of an lvalue and the object designated by a pointer
+            // are both represented by the pointer value
+            UnaryOps::Addr => self.gen(ast),
+            UnaryOps::Deref => self.gen(ast),
+        }
     }
 
     pub unsafe fn gen_binary_op(
@@ -250,9 +1195,8 @@ impl Codegen {
         rhs: &AST,
         op: &BinaryOps,
     ) -> Option<(LLVMValueRef, Option<Type>)> {
-        // TODO: assign
         if let BinaryOps::Assign = op {
-            return self.gen_assign(&inside_load(lhs), rhs);
+            return self.gen_assign(lhs, rhs);
         }
 
         let (lhs_val, lhs_ty) = self.gen(&*lhs).unwrap();
@@ -260,25 +1204,15 @@ impl Codegen {
         let lhs_ty = lhs_ty.unwrap();
         let rhs_ty = rhs_ty.unwrap();
 
-        // TODO: type casting
-        // support double binary
-
-        // debug
-        //println!("type");
-        //println!("l: {:?}", lhs_ty);
-        //println!("r: {:?}", rhs_ty);
-
-        // TODO: support pointer support
-        if matches!(&lhs_ty, Type::Ptr(_)) {
-            return self.gen_ptr_binary_op(lhs_val, rhs_val, lhs_ty, op);
-        } else if matches!(&rhs_ty, Type::Ptr(_)) {
-            return self.gen_ptr_binary_op(lhs_val, rhs_val, rhs_ty, op);
+        // sema puts the pointer on the lhs of pointer arithmetic
+        if lhs_ty.is_ptr() {
+            return self.gen_ptr_binary_op(lhs_val, rhs_val, lhs_ty, rhs_ty, op);
         }
 
-        //let casted_lhs = self.typecast(lhs_val, LLVMInt64Type());
-        //let casted_rhs = self.typecast(rhs_val, LLVMInt64Type());
-
-        // TODO: cast types to the same int type (i32, i64)
+        // sema has already converted both operands to the same arithmetic type
+        if lhs_ty.is_flonum() {
+            return self.gen_float_binary_op(lhs_val, rhs_val, lhs_ty, op);
+        }
         self.gen_int_binary_op(&lhs_val, &rhs_val, lhs_ty, op)
     }
 
@@ -287,17 +1221,25 @@ impl Codegen {
         lhs_val: LLVMValueRef,
         rhs_val: LLVMValueRef,
         ty: Type,
+        rhs_ty: Type,
         op: &BinaryOps,
     ) -> Option<(LLVMValueRef, Option<Type>)> {
+        // pointers are compared as unsigned integers
+        if let BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le = op {
+            return self.gen_int_binary_op(&lhs_val, &rhs_val, Type::ULong, op);
+        }
+        if rhs_ty.is_ptr() {
+            return self.gen_ptr_diff(lhs_val, rhs_val, &ty);
+        }
+        // the index has been converted to long
+        let rhs_val = match &ty {
+            Type::Ptr(base_ty) => self.scale_vla_index(rhs_val, base_ty),
+            _ => rhs_val,
+        };
         let mut numidx = vec![match *op {
             BinaryOps::Add => rhs_val,
-            BinaryOps::Sub => LLVMBuildSub(
-                self.builder,
-                self.make_int(0, true).unwrap().0,
-                rhs_val,
-                cstr("sub").as_ptr(),
-            ),
-            _ => panic!(),
+            BinaryOps::Sub => LLVMBuildNeg(self.builder, rhs_val, cstr("neg").as_ptr()),
+            _ => panic!("Unsupported pointer op"),
         }];
         let ret = LLVMBuildGEP(
             self.builder,
@@ -309,6 +1251,32 @@ impl Codegen {
         Some((ret, Some(ty)))
     }
 
+    // (lhs - rhs) / size of the element, as ptrdiff_t
+    unsafe fn gen_ptr_diff(&mut self, lhs_val: LLVMValueRef, rhs_val: LLVMValueRef, ty: &Type) -> Option<(LLVMValueRef, Option<Type>)> {
+        let base_ty = match ty {
+            Type::Ptr(base_ty) => base_ty,
+            _ => panic!("{} is not a pointer", ty),
+        };
+        let lhs = LLVMBuildPtrToInt(self.builder, lhs_val, LLVMInt64Type(), cstr("ptrtoint").as_ptr());
+        let rhs = LLVMBuildPtrToInt(self.builder, rhs_val, LLVMInt64Type(), cstr("ptrtoint").as_ptr());
+        let diff = LLVMBuildSub(self.builder, lhs, rhs, cstr("sub").as_ptr());
+        // the size of a variable length array is its count of innermost elements times their size
+        let size = match &**base_ty {
+            Type::Vla(..) => {
+                let mut inner_ty = &**base_ty;
+                while let Type::Vla(elem_ty, _) = inner_ty {
+                    inner_ty = elem_ty;
+                }
+                let inner_size = self.make_int(inner_ty.size() as u64, &Type::Long).unwrap().0;
+                let count = self.gen_vla_count(base_ty);
+                LLVMBuildMul(self.builder, count, inner_size, cstr("vla.size").as_ptr())
+            }
+            _ => self.make_int(base_ty.size() as u64, &Type::Long).unwrap().0,
+        };
+        let ret = LLVMBuildExactSDiv(self.builder, diff, size, cstr("ptrdiff").as_ptr());
+        Some((ret, Some(Type::Long)))
+    }
+
     pub unsafe fn gen_int_binary_op(
         &mut self,
         lhs_val: &LLVMValueRef,
@@ -320,6 +1288,9 @@ impl Codegen {
             BinaryOps::Add => LLVMBuildAdd(self.builder, *lhs_val, *rhs_val, cstr("add").as_ptr()),
             BinaryOps::Sub => LLVMBuildSub(self.builder, *lhs_val, *rhs_val, cstr("sub").as_ptr()),
             BinaryOps::Mul => LLVMBuildMul(self.builder, *lhs_val, *rhs_val, cstr("mul").as_ptr()),
+            BinaryOps::Div if ty.is_unsigned() => {
+                LLVMBuildUDiv(self.builder, *lhs_val, *rhs_val, cstr("udiv").as_ptr())
+            }
             BinaryOps::Div => LLVMBuildSDiv(self.builder, *lhs_val, *rhs_val, cstr("sdiv").as_ptr()),
             BinaryOps::Eq => LLVMBuildICmp(
                 self.builder,
@@ -337,14 +1308,22 @@ impl Codegen {
             ),
             BinaryOps::Lt => LLVMBuildICmp(
                 self.builder,
-                llvm::LLVMIntPredicate::LLVMIntSLT,
+                if ty.is_unsigned() {
+                    llvm::LLVMIntPredicate::LLVMIntULT
+                } else {
+                    llvm::LLVMIntPredicate::LLVMIntSLT
+                },
                 *lhs_val,
                 *rhs_val,
                 cstr("lt").as_ptr(),
             ),
             BinaryOps::Le => LLVMBuildICmp(
                 self.builder,
-                llvm::LLVMIntPredicate::LLVMIntSLE,
+                if ty.is_unsigned() {
+                    llvm::LLVMIntPredicate::LLVMIntULE
+                } else {
+                    llvm::LLVMIntPredicate::LLVMIntSLE
+                },
                 *lhs_val,
                 *rhs_val,
                 cstr("le").as_ptr(),
@@ -352,50 +1331,113 @@ impl Codegen {
 
             _ => panic!("Unsupported bianry op"),
         };
-        // TODO: lhs_ty is OK?
-        Some((res, Some(ty)))
+        self.make_binary_op_result(res, ty, op)
+    }
+
+    unsafe fn gen_float_binary_op(
+        &mut self,
+        lhs_val: LLVMValueRef,
+        rhs_val: LLVMValueRef,
+        ty: Type,
+        op: &BinaryOps,
+    ) -> Option<(LLVMValueRef, Option<Type>)> {
+        let fcmp = |builder, pred, name: &'static str| {
+            LLVMBuildFCmp(builder, pred, lhs_val, rhs_val, cstr(name).as_ptr())
+        };
+        let res = match op {
+            BinaryOps::Add => LLVMBuildFAdd(self.builder, lhs_val, rhs_val, cstr("fadd").as_ptr()),
+            BinaryOps::Sub => LLVMBuildFSub(self.builder, lhs_val, rhs_val, cstr("fsub").as_ptr()),
+            BinaryOps::Mul => LLVMBuildFMul(self.builder, lhs_val, rhs_val, cstr("fmul").as_ptr()),
+            BinaryOps::Div => LLVMBuildFDiv(self.builder, lhs_val, rhs_val, cstr("fdiv").as_ptr()),
+            BinaryOps::Eq => fcmp(self.builder, llvm::LLVMRealPredicate::LLVMRealOEQ, "feql"),
+            // != is true when either operand is NaN
+            BinaryOps::Ne => fcmp(self.builder, llvm::LLVMRealPredicate::LLVMRealUNE, "fne"),
+            BinaryOps::Lt => fcmp(self.builder, llvm::LLVMRealPredicate::LLVMRealOLT, "flt"),
+            BinaryOps::Le => fcmp(self.builder, llvm::LLVMRealPredicate::LLVMRealOLE, "fle"),
+            _ => panic!("Unsupported bianry op"),
+        };
+        self.make_binary_op_result(res, ty, op)
+    }
+
+    // comparisons yield i1, which is extended to int as C requires
+    unsafe fn make_binary_op_result(
+        &mut self,
+        res: LLVMValueRef,
+        ty: Type,
+        op: &BinaryOps,
+    ) -> Option<(LLVMValueRef, Option<Type>)> {
+        match op {
+            BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le => {
+                let res = LLVMBuildZExt(self.builder, res, LLVMInt32Type(), cstr("zext").as_ptr());
+                Some((res, Some(Type::Int)))
+            }
+            _ => Some((res, Some(ty))),
+        }
     }
 
     pub unsafe fn gen_load(&mut self, ast: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
-        // TODO: support other types than AST::Variable
-        match ast {
-            AST::Variable(ref name) => {
-                let (val, ty) = self.gen(ast).unwrap();
-                let ty = ty.unwrap();
-                let ret = LLVMBuildLoad(self.builder, val, cstr("var").as_ptr());
-                match ty {
-                    Type::Ptr(origin_ty) => Some((ret, Some(*origin_ty))),
-                    _ => panic!(),
+        if let AST::Member(expr, name) = ast {
+            let (ptr, ty, bit_field) = self.gen_member_ref(expr, name);
+            let val = match bit_field {
+                Some(bit_field) => self.gen_bit_field_load(ptr, &ty, bit_field),
+                None => {
+                    let val = LLVMBuildLoad(self.builder, ptr, cstr("var").as_ptr());
+                    self.set_access_qualifiers(val, &ty);
+                    val
                 }
-            },
-            _ => {
-                let (val, ty) = self.gen(ast).unwrap();
-                let ret = LLVMBuildLoad(self.builder, val, cstr("var").as_ptr());
-                Some((ret, Some(Type::Ptr(Box::new(ty.unwrap())))))
-            },
+            };
+            return Some((val, Some(ty.unqualified())));
+        }
+        // an lvalue is generated as its address, whose type is a pointer to the object
+        let (val, ty) = self.gen(ast).unwrap();
+        let ret = LLVMBuildLoad(self.builder, val, cstr("var").as_ptr());
+        match ty.unwrap() {
+            Type::Ptr(origin_ty) => {
+                self.set_access_qualifiers(ret, &origin_ty);
+                Some((ret, Some(origin_ty.unqualified())))
+            }
+            _ => panic!(),
+        }
+    }
+
+    unsafe fn gen_cast(&mut self, ast: &AST, ty: &Type) -> Option<(LLVMValueRef, Option<Type>)> {
+        // the operand is evaluated only for its side effects
+        if ty.is_void() {
+            self.gen(ast);
+            return None;
         }
+        let (val, from) = self.gen(ast).unwrap();
+        let val = self.typecast(val, &from.unwrap(), ty);
+        Some((val, Some(ty.clone())))
     }
 
     pub unsafe fn gen_var(&mut self, name: &String) -> Option<(LLVMValueRef, Option<Type>)> {
-        // TODO: support scope
-        if self.local_varmap.is_empty() {
-            panic!();
-        }
-        let mut i = (self.local_varmap.len() - 1) as isize;
-        while i >= 0 {
-            let var_info_opt = self.local_varmap[i as usize].get(name);
-            match var_info_opt {
-                Some(ref var_info) => {
-                    return Some((
-                        var_info.llvm_val,
-                        Some(Type::Ptr(Box::new(var_info.ty.clone()))),
-                    ));
-                }
-                _ => (),
+        for local_varmap in self.local_varmap.iter().rev() {
+            if let Some(var_info) = local_varmap.get(name) {
+                return Some((
+                    var_info.llvm_val,
+                    Some(Type::Ptr(Box::new(var_info.ty.clone()))),
+                ));
             }
-            i -= 1;
         }
-        panic!("local variable not found");
+        // a global variable, or a function whose value is its address
+        if let Some(var_info) = self.global_varmap.get(name) {
+            return Some((var_info.llvm_val, Some(Type::Ptr(Box::new(var_info.ty.clone())))));
+        }
+        panic!("variable not found: {}", name);
+    }
+
+    // a string literal is a private constant array of char
+    unsafe fn gen_str(&mut self, s: &[u8]) -> Option<(LLVMValueRef, Option<Type>)> {
+        let val = LLVMConstStringInContext(self.context, s.as_ptr() as *const _, s.len() as u32, 0);
+        let str = LLVMAddGlobal(self.module, LLVMTypeOf(val), cstr(".str").as_ptr());
+        LLVMSetInitializer(str, val);
+        LLVMSetLinkage(str, llvm::LLVMLinkage::LLVMPrivateLinkage);
+        LLVMSetGlobalConstant(str, 1);
+        LLVMSetUnnamedAddress(str, llvm::LLVMUnnamedAddr::LLVMGlobalUnnamedAddr);
+        LLVMSetAlignment(str, 1);
+        let ty = Type::Array(Box::new(Type::Char), s.len() as i32 + 1);
+        Some((str, Some(Type::Ptr(Box::new(ty)))))
     }
 
     pub unsafe fn gen_assign(
@@ -403,16 +1445,32 @@ impl Codegen {
         lhs: &AST,
         rhs: &AST,
     ) -> Option<(LLVMValueRef, Option<Type>)> {
-        let (rhs_val, ty) = self.gen(rhs).unwrap();
-        let (dst, dst_ty) = self.gen(lhs).unwrap();
-        LLVMBuildStore(self.builder, rhs_val, dst);
-        let load = LLVMBuildLoad(self.builder, dst, cstr("load").as_ptr());
-        // TODO: ty is OK?
-        Some((load, dst_ty))
+        let rhs_val = self.gen(rhs).unwrap().0;
+        let (dst, ty, bit_field) = match lhs {
+            AST::Member(expr, name) => self.gen_member_ref(expr, name),
+            _ => match self.gen(lhs).unwrap() {
+                (dst, Some(Type::Ptr(ty))) => (dst, *ty, None),
+                _ => panic!("Cannot assign to a non-lvalue"),
+            },
+        };
+        // the value of an assignment is the value stored, which may be truncated to a bit-field
+        let load = match bit_field {
+            Some(bit_field) => {
+                self.gen_bit_field_store(rhs_val, dst, &ty, bit_field);
+                self.gen_bit_field_load(dst, &ty, bit_field)
+            }
+            // not read back, which would be another access to a volatile object
+            None => {
+                let store = LLVMBuildStore(self.builder, rhs_val, dst);
+                self.set_access_qualifiers(store, &ty);
+                rhs_val
+            }
+        };
+        Some((load, Some(ty.unqualified())))
     }
 
     pub unsafe fn gen_if(&mut self, cond: &AST, then: &AST, els: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
-        let cond_val = self.gen(cond).unwrap().0;
+        let cond_val = self.gen_cond(cond);
         let func = self.cur_func.unwrap();
         let bb_then = LLVMAppendBasicBlock(func, cstr("then").as_ptr());
         let bb_else = LLVMAppendBasicBlock(func, cstr("else").as_ptr());
@@ -433,7 +1491,14 @@ impl Codegen {
     }
 
     pub unsafe fn gen_for(&mut self, init: &AST, cond: &AST, step: &AST, body: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
-        self.gen(init);
+        // variables declared in the init clause are in a scope enclosing the loop
+        self.local_varmap.push(HashMap::new());
+        match init {
+            AST::Block(block) => self.gen_block_items(block),
+            _ => {
+                self.gen(init);
+            }
+        }
         let func = self.cur_func.unwrap();
         let bb_begin = LLVMAppendBasicBlock(func, cstr("begin").as_ptr());
         let bb_body = LLVMAppendBasicBlock(func, cstr("body").as_ptr());
@@ -441,7 +1506,7 @@ impl Codegen {
         let bb_end = LLVMAppendBasicBlock(func, cstr("end").as_ptr());
         LLVMBuildBr(self.builder, bb_begin);
         LLVMPositionBuilderAtEnd(self.builder, bb_begin);
-        let cond_val = self.gen(cond).unwrap().0;
+        let cond_val = self.gen_cond(cond);
         LLVMBuildCondBr(self.builder, cond_val, bb_body, bb_end);
         LLVMPositionBuilderAtEnd(self.builder, bb_body);
         self.gen(body);
@@ -454,23 +1519,331 @@ impl Codegen {
             LLVMBuildBr(self.builder, bb_begin);
         }
         LLVMPositionBuilderAtEnd(self.builder, bb_end);
+        self.local_varmap.pop();
+        None
+    }
+
+    unsafe fn gen_while(&mut self, cond: &AST, body: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
+        let func = self.cur_func.unwrap();
+        let bb_begin = LLVMAppendBasicBlock(func, cstr("begin").as_ptr());
+        let bb_body = LLVMAppendBasicBlock(func, cstr("body").as_ptr());
+        let bb_end = LLVMAppendBasicBlock(func, cstr("end").as_ptr());
+        LLVMBuildBr(self.builder, bb_begin);
+        LLVMPositionBuilderAtEnd(self.builder, bb_begin);
+        let cond_val = self.gen_cond(cond);
+        LLVMBuildCondBr(self.builder, cond_val, bb_body, bb_end);
+        LLVMPositionBuilderAtEnd(self.builder, bb_body);
+        self.gen(body);
+        if !is_exist_terminator(self.builder) {
+            LLVMBuildBr(self.builder, bb_begin);
+        }
+        LLVMPositionBuilderAtEnd(self.builder, bb_end);
         None
     }
 
     pub unsafe fn gen_return(&mut self, ast: &AST) -> Option<(LLVMValueRef, Option<Type>)> {
-        let ret_val = self.gen(ast);
-        LLVMBuildRet(self.builder, ret_val.unwrap().0);
+        let (ret_val, ty) = self.gen(ast).unwrap();
+        let ty = ty.unwrap();
+        match self.cur_func_abi.clone().unwrap().ret {
+            ArgKind::Direct => {
+                LLVMBuildRet(self.builder, ret_val);
+            }
+            ArgKind::Coerced(types) => {
+                let tmp = self.spill(ret_val, &ty);
+                let coerced_ty = self.coerced_ty(&types);
+                let ptr = LLVMBuildPointerCast(
+                    self.builder,
+                    tmp,
+                    LLVMPointerType(coerced_ty, 0),
+                    cstr("coerce").as_ptr(),
+                );
+                let ret_val = LLVMBuildLoad(self.builder, ptr, cstr("coerce").as_ptr());
+                LLVMBuildRet(self.builder, ret_val);
+            }
+            ArgKind::Memory => {
+                LLVMBuildStore(self.builder, ret_val, self.cur_sret.unwrap());
+                LLVMBuildRetVoid(self.builder);
+            }
+        }
         None
     }
 
-    pub unsafe fn make_int(
+    unsafe fn gen_func_call(
         &mut self,
-        n: u64,
-        is_unsigned: bool,
+        callee: &AST,
+        args: &[AST],
     ) -> Option<(LLVMValueRef, Option<Type>)> {
+        // the callee is the address of a function, so calling a function pointer is an indirect call
+        let (func, func_ty) = match self.gen(callee).unwrap() {
+            (func, Some(Type::Ptr(func_ty))) => (func, *func_ty),
+            _ => panic!("Called object is not a function"),
+        };
+        let mut args: Vec<(LLVMValueRef, Type)> = args
+            .iter()
+            .map(|arg| {
+                let (val, ty) = self.gen(arg).unwrap();
+                (val, ty.unwrap())
+            })
+            .collect();
+        // arguments for ... are passed as if there were parameters of their types
+        let func_ty = match func_ty {
+            Type::Func(ret_ty, mut param_types, param_names, true) => {
+                param_types.extend(args[param_types.len()..].iter().map(|(_, ty)| ty.clone()));
+                Type::Func(ret_ty, param_types, param_names, true)
+            }
+            func_ty => func_ty,
+        };
+        let ret_ty = match &func_ty {
+            Type::Func(ret_ty, ..) => (**ret_ty).clone(),
+            _ => panic!("Called object is not a function"),
+        };
+        let func_abi = self.func_abi(&func_ty);
+        let mut arg_vals = Vec::new();
+        let mut sret = None;
+        if let ArgKind::Memory = func_abi.ret {
+            let tmp = self.build_entry_alloca(&ret_ty, "sret");
+            arg_vals.push(tmp);
+            sret = Some(tmp);
+        }
+        // scalar arguments are assigned to registers by the backend (SysV x86-64)
+        for ((val, ty), kind) in args.drain(..).zip(func_abi.params.iter()) {
+            match kind {
+                ArgKind::Direct => arg_vals.push(val),
+                ArgKind::Coerced(types) => {
+                    let tmp = self.spill(val, &ty);
+                    let coerced_ty = self.coerced_struct_ty(types);
+                    let ptr = LLVMBuildPointerCast(
+                        self.builder,
+                        tmp,
+                        LLVMPointerType(coerced_ty, 0),
+                        cstr("coerce").as_ptr(),
+                    );
+                    for i in 0..types.len() {
+                        let field =
+                            LLVMBuildStructGEP(self.builder, ptr, i as u32, cstr("coerce").as_ptr());
+                        arg_vals.push(LLVMBuildLoad(self.builder, field, cstr("coerce").as_ptr()));
+                    }
+                }
+                ArgKind::Memory => {
+                    let tmp = self.spill(val, &ty);
+                    arg_vals.push(tmp);
+                }
+            }
+        }
+        // a call returning void must not be named
+        let is_void = ret_ty.is_void() || sret.is_some();
+        let ret = LLVMBuildCall(
+            self.builder,
+            func,
+            arg_vals.as_mut_slice().as_mut_ptr(),
+            arg_vals.len() as u32,
+            cstr(if is_void { "" } else { "call" }).as_ptr(),
+        );
+        self.add_abi_attrs(ret, &func_ty, true);
+        match func_abi.ret {
+            ArgKind::Direct => Some((ret, Some(ret_ty))),
+            ArgKind::Coerced(types) => {
+                let tmp = self.build_entry_alloca(&ret_ty, "tmp");
+                LLVMSetAlignment(tmp, ret_ty.align().max(16) as u32);
+                let coerced_ty = self.coerced_ty(&types);
+                let ptr = LLVMBuildPointerCast(
+                    self.builder,
+                    tmp,
+                    LLVMPointerType(coerced_ty, 0),
+                    cstr("coerce").as_ptr(),
+                );
+                LLVMBuildStore(self.builder, ret, ptr);
+                let ret = LLVMBuildLoad(self.builder, tmp, cstr("call").as_ptr());
+                Some((ret, Some(ret_ty)))
+            }
+            ArgKind::Memory => {
+                let ret = LLVMBuildLoad(self.builder, sret.unwrap(), cstr("call").as_ptr());
+                Some((ret, Some(ret_ty)))
+            }
+        }
+    }
+
+    // va_start, va_end and va_copy are the LLVM intrinsics, which take va_lists as i8 *
+    unsafe fn gen_va_intrinsic(&mut self, name: &'static str, args: &[&AST]) -> Option<(LLVMValueRef, Option<Type>)> {
+        let i8_ptr_ty = LLVMPointerType(LLVMInt8Type(), 0);
+        let func = self.get_intrinsic(name, LLVMVoidType(), &mut vec![i8_ptr_ty; args.len()]);
+        let mut vals = Vec::new();
+        for arg in args {
+            let val = self.gen(arg).unwrap().0;
+            vals.push(LLVMBuildPointerCast(self.builder, val, i8_ptr_ty, cstr("ap").as_ptr()));
+        }
+        LLVMBuildCall(self.builder, func, vals.as_mut_ptr(), vals.len() as u32, cstr("").as_ptr());
+        None
+    }
+
+    // va_arg as clang lowers it for the SysV x86-64 ABI: an argument is read from the register
+    // save area (filled by va_start) if the registers it needs for its class were not used up,
+    // and from the overflow area on the stack otherwise
+    unsafe fn gen_va_arg(&mut self, ap: &AST, ty: &Type) -> Option<(LLVMValueRef, Option<Type>)> {
+        let ap_val = self.gen(ap).unwrap().0;
+        let i8_ptr_ty = LLVMPointerType(LLVMInt8Type(), 0);
+        let llvm_ptr_ty = LLVMPointerType(self.type_to_llvmty(ty), 0);
+        // the eightbytes of the argument, or None if it is passed in memory
+        let classes = if ty.is_struct() {
+            abi::classify(ty)
+        } else if matches!(ty, Type::LongDouble) {
+            None
+        } else if ty.is_flonum() {
+            Some(vec![abi::ArgClass::Sse(ty.size(), matches!(ty, Type::Double))])
+        } else {
+            Some(vec![abi::ArgClass::Integer(ty.size())])
+        };
+        let func = self.cur_func.unwrap();
+        let bb_end = LLVMAppendBasicBlock(func, cstr("va_arg.end").as_ptr());
+        let mut addrs = Vec::new();
+        let mut bbs = Vec::new();
+        if let Some(classes) = classes {
+            let need_int = classes
+                .iter()
+                .filter(|class| matches!(class, abi::ArgClass::Integer(_)))
+                .count() as u64;
+            let need_sse = classes.len() as u64 - need_int;
+            let gp_offset_ptr = self.va_list_field(ap_val, 0, LLVMInt32Type());
+            let fp_offset_ptr = self.va_list_field(ap_val, 4, LLVMInt32Type());
+            let gp_offset = LLVMBuildLoad(self.builder, gp_offset_ptr, cstr("gp_offset").as_ptr());
+            let fp_offset = LLVMBuildLoad(self.builder, fp_offset_ptr, cstr("fp_offset").as_ptr());
+            // the area saves 6 general purpose registers of 8 bytes, then 8 SSE registers of 16 bytes
+            let gp_fits = LLVMBuildICmp(
+                self.builder,
+                llvm::LLVMIntPredicate::LLVMIntULE,
+                gp_offset,
+                LLVMConstInt(LLVMInt32Type(), 48 - 8 * need_int, 0),
+                cstr("gp_fits").as_ptr(),
+            );
+            let fp_fits = LLVMBuildICmp(
+                self.builder,
+                llvm::LLVMIntPredicate::LLVMIntULE,
+                fp_offset,
+                LLVMConstInt(LLVMInt32Type(), 176 - 16 * need_sse, 0),
+                cstr("fp_fits").as_ptr(),
+            );
+            let fits = LLVMBuildAnd(self.builder, gp_fits, fp_fits, cstr("fits").as_ptr());
+            let bb_reg = LLVMAppendBasicBlock(func, cstr("va_arg.reg").as_ptr());
+            let bb_mem = LLVMAppendBasicBlock(func, cstr("va_arg.mem").as_ptr());
+            LLVMBuildCondBr(self.builder, fits, bb_reg, bb_mem);
+
+            LLVMPositionBuilderAtEnd(self.builder, bb_reg);
+            let reg_save_area_ptr = self.va_list_field(ap_val, 16, i8_ptr_ty);
+            let reg_save_area = LLVMBuildLoad(self.builder, reg_save_area_ptr, cstr("reg_save_area").as_ptr());
+            let addr = if ty.is_struct() {
+                // the eightbytes of a struct may be in registers of both classes,
+                // so they are copied into a temporary
+                let tmp = self.build_entry_alloca(ty, "va_arg");
+                LLVMSetAlignment(tmp, ty.align().max(8) as u32);
+                let tmp = LLVMBuildPointerCast(self.builder, tmp, i8_ptr_ty, cstr("va_arg").as_ptr());
+                let (mut gp, mut fp) = (gp_offset, fp_offset);
+                for (i, class) in classes.iter().enumerate() {
+                    let (offset, size) = match class {
+                        abi::ArgClass::Integer(size) => {
+                            let offset = gp;
+                            gp = LLVMBuildAdd(self.builder, gp, LLVMConstInt(LLVMInt32Type(), 8, 0), cstr("gp").as_ptr());
+                            (offset, size)
+                        }
+                        abi::ArgClass::Sse(size, _) => {
+                            let offset = fp;
+                            fp = LLVMBuildAdd(self.builder, fp, LLVMConstInt(LLVMInt32Type(), 16, 0), cstr("fp").as_ptr());
+                            (offset, size)
+                        }
+                    };
+                    let mut idx = [offset];
+                    let src = LLVMBuildGEP(self.builder, reg_save_area, idx.as_mut_ptr(), 1, cstr("src").as_ptr());
+                    let mut idx = [LLVMConstInt(LLVMInt64Type(), 8 * i as u64, 0)];
+                    let dst = LLVMBuildGEP(self.builder, tmp, idx.as_mut_ptr(), 1, cstr("dst").as_ptr());
+                    LLVMBuildMemCpy(self.builder, dst, 1, src, 1, LLVMConstInt(LLVMInt64Type(), *size as u64, 0));
+                }
+                tmp
+            } else {
+                let mut idx = [if need_int == 1 { gp_offset } else { fp_offset }];
+                LLVMBuildGEP(self.builder, reg_save_area, idx.as_mut_ptr(), 1, cstr("va_arg").as_ptr())
+            };
+            if need_int > 0 {
+                let next = LLVMConstInt(LLVMInt32Type(), 8 * need_int, 0);
+                let next = LLVMBuildAdd(self.builder, gp_offset, next, cstr("gp_offset").as_ptr());
+                LLVMBuildStore(self.builder, next, gp_offset_ptr);
+            }
+            if need_sse > 0 {
+                let next = LLVMConstInt(LLVMInt32Type(), 16 * need_sse, 0);
+                let next = LLVMBuildAdd(self.builder, fp_offset, next, cstr("fp_offset").as_ptr());
+                LLVMBuildStore(self.builder, next, fp_offset_ptr);
+            }
+            addrs.push(LLVMBuildPointerCast(self.builder, addr, llvm_ptr_ty, cstr("va_arg").as_ptr()));
+            bbs.push(LLVMGetInsertBlock(self.builder));
+            LLVMBuildBr(self.builder, bb_end);
+            LLVMPositionBuilderAtEnd(self.builder, bb_mem);
+        }
+        // arguments on the stack are aligned to 8 bytes, or more if the type requires
+        let overflow_arg_area_ptr = self.va_list_field(ap_val, 8, i8_ptr_ty);
+        let mut overflow_arg_area =
+            LLVMBuildLoad(self.builder, overflow_arg_area_ptr, cstr("overflow_arg_area").as_ptr());
+        let align = ty.align().max(8) as u64;
+        if align > 8 {
+            let addr = LLVMBuildPtrToInt(self.builder, overflow_arg_area, LLVMInt64Type(), cstr("addr").as_ptr());
+            let addr = LLVMBuildAdd(self.builder, addr, LLVMConstInt(LLVMInt64Type(), align - 1, 0), cstr("addr").as_ptr());
+            let addr = LLVMBuildAnd(self.builder, addr, LLVMConstInt(LLVMInt64Type(), !(align - 1), 0), cstr("addr").as_ptr());
+            overflow_arg_area = LLVMBuildIntToPtr(self.builder, addr, i8_ptr_ty, cstr("overflow_arg_area").as_ptr());
+        }
+        let mut idx = [LLVMConstInt(LLVMInt64Type(), types::align_to(ty.size(), 8) as u64, 0)];
+        let next = LLVMBuildGEP(self.builder, overflow_arg_area, idx.as_mut_ptr(), 1, cstr("overflow_arg_area").as_ptr());
+        LLVMBuildStore(self.builder, next, overflow_arg_area_ptr);
+        addrs.push(LLVMBuildPointerCast(self.builder, overflow_arg_area, llvm_ptr_ty, cstr("va_arg").as_ptr()));
+        bbs.push(LLVMGetInsertBlock(self.builder));
+        LLVMBuildBr(self.builder, bb_end);
+
+        LLVMPositionBuilderAtEnd(self.builder, bb_end);
+        let addr = LLVMBuildPhi(self.builder, llvm_ptr_ty, cstr("va_arg").as_ptr());
+        LLVMAddIncoming(addr, addrs.as_mut_ptr(), bbs.as_mut_ptr(), addrs.len() as u32);
+        let val = LLVMBuildLoad(self.builder, addr, cstr("va_arg").as_ptr());
+        Some((val, Some(ty.clone())))
+    }
+
+    // a pointer to the field at the byte offset in the va_list
+    unsafe fn va_list_field(&mut self, ap: LLVMValueRef, offset: u64, field_ty: LLVMTypeRef) -> LLVMValueRef {
+        let bytes = LLVMBuildPointerCast(self.builder, ap, LLVMPointerType(LLVMInt8Type(), 0), cstr("ap").as_ptr());
+        let mut idx = [LLVMConstInt(LLVMInt64Type(), offset, 0)];
+        let field = LLVMBuildGEP(self.builder, bytes, idx.as_mut_ptr(), 1, cstr("ap").as_ptr());
+        LLVMBuildPointerCast(self.builder, field, LLVMPointerType(field_ty, 0), cstr("ap").as_ptr())
+    }
+
+    unsafe fn gen_cond(&mut self, ast: &AST) -> LLVMValueRef {
+        // no condition (e.g. for(;;)) means always true
+        if let AST::Nil = ast {
+            return LLVMConstInt(LLVMInt1Type(), 1, 0);
+        }
+        // a scalar condition is true when it compares unequal to 0
+        let (val, ty) = self.gen(ast).unwrap();
+        let zero = LLVMConstNull(LLVMTypeOf(val));
+        if ty.unwrap().is_flonum() {
+            LLVMBuildFCmp(
+                self.builder,
+                llvm::LLVMRealPredicate::LLVMRealUNE,
+                val,
+                zero,
+                cstr("cond").as_ptr(),
+            )
+        } else {
+            LLVMBuildICmp(
+                self.builder,
+                llvm::LLVMIntPredicate::LLVMIntNE,
+                val,
+                zero,
+                cstr("cond").as_ptr(),
+            )
+        }
+    }
+
+    pub unsafe fn make_int(&mut self, n: u64, ty: &Type) -> Option<(LLVMValueRef, Option<Type>)> {
         Some((
-            LLVMConstInt(LLVMInt32Type(), n, if is_unsigned { 1 } else { 0 }),
-            Some(Type::Int),
+            LLVMConstInt(self.type_to_llvmty(ty), n, !ty.is_unsigned() as i32),
+            Some(ty.clone()),
         ))
     }
+
+    unsafe fn make_float(&mut self, f: f64, ty: &Type) -> Option<(LLVMValueRef, Option<Type>)> {
+        Some((LLVMConstReal(self.type_to_llvmty(ty), f), Some(ty.clone())))
+    }
 }
@@ -0,0 +1,281 @@
+use crate::node::{AST, BinaryOps, TypeQuery, UnaryOps};
+use crate::sema;
+use crate::types;
+use crate::types::Type;
+
+use std::fmt;
+
+// Arithmetic on constants folded at compile time, with the semantics of the types of
+// the target (x86-64 Linux): sema walks a checked expression, in which every
+// conversion is an explicit AST::Cast, and folds it with these operations.
+//
+// The parser folds the integer constant expressions it needs to build types (e.g. the
+// value of an enumerator) before semantic analysis with eval_int_const, which gives the
+// operands their types itself. Without the types of variables, an operand of sizeof,
+// _Alignof or _Generic there must be a constant or a string literal.
+
+// the value of a constant expression
+#[derive(Debug, Clone)]
+pub enum ConstValue {
+    // an integer, or a pointer converted from one; the value is sign- or zero-extended
+    // from the width of the type, so an unsigned long above LONG_MAX is negative here
+    Int(i64, Type),
+    // a floating constant; long double is computed with the precision of double
+    Float(f64, Type),
+    // an address constant (e.g. &x + 1), which is folded by codegen with the address of
+    // the object or function once it is known
+    Addr(AST),
+}
+
+#[derive(Debug, Clone)]
+pub enum ConstError {
+    // the expression is not a constant expression (e.g. it reads a variable)
+    NotConstant(String),
+    // a constant expression which is invalid where it is used (e.g. it overflows, or it
+    // is not an integer where one is required)
+    Invalid(String),
+}
+
+pub type ConstResult = Result<ConstValue, ConstError>;
+
+impl fmt::Display for ConstError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstError::NotConstant(msg) | ConstError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl ConstValue {
+    // the value of an integer constant expression
+    pub fn to_int(&self) -> Result<i64, ConstError> {
+        match self {
+            ConstValue::Int(val, ty) if ty.is_integer() => Ok(*val),
+            _ => Err(ConstError::Invalid("Expression is not an integer constant expression".to_string())),
+        }
+    }
+
+    // whether the value is zero, as tested by conditions; an address is never null
+    pub fn is_zero(&self) -> bool {
+        match self {
+            ConstValue::Int(val, _) => *val == 0,
+            ConstValue::Float(val, _) => *val == 0.0,
+            ConstValue::Addr(_) => false,
+        }
+    }
+}
+
+// an integer truncated to the width of ty, and extended back according to its signedness
+pub fn normalize(val: i64, ty: &Type) -> i64 {
+    if ty.is_bool() {
+        return (val != 0) as i64;
+    }
+    let bits = ty.size() * 8;
+    if bits >= 64 {
+        return val;
+    }
+    if ty.is_unsigned() {
+        val & ((1i64 << bits) - 1)
+    } else {
+        val << (64 - bits) >> (64 - bits)
+    }
+}
+
+// the value of an integer as a wider integer, interpreting the bits as ty does
+fn widen(val: i64, ty: &Type) -> i128 {
+    if ty.is_unsigned() {
+        val as u64 as i128
+    } else {
+        val as i128
+    }
+}
+
+// the result of an arithmetic operation on the type ty, which is an error if it is signed
+// and the result does not fit in it; an unsigned result wraps around
+fn fit(val: i128, ty: &Type) -> ConstResult {
+    if !ty.is_unsigned() {
+        let bits = ty.size() * 8;
+        let (min, max) = (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1);
+        if val < min || max < val {
+            return Err(ConstError::Invalid(format!("Integer overflow in constant expression of type {}", ty)));
+        }
+    }
+    Ok(ConstValue::Int(normalize(val as i64, ty), ty.clone()))
+}
+
+// both operands have been converted to ty by the usual arithmetic conversions
+pub fn int_binary_op(lhs: i64, rhs: i64, ty: &Type, op: &BinaryOps) -> ConstResult {
+    let (l, r) = (widen(lhs, ty), widen(rhs, ty));
+    let val = match op {
+        BinaryOps::Add => l + r,
+        BinaryOps::Sub => l - r,
+        BinaryOps::Mul => l * r,
+        BinaryOps::Div => {
+            if r == 0 {
+                return Err(ConstError::Invalid("Division by zero in constant expression".to_string()));
+            }
+            l / r
+        }
+        BinaryOps::Eq => return Ok(ConstValue::Int((l == r) as i64, Type::Int)),
+        BinaryOps::Ne => return Ok(ConstValue::Int((l != r) as i64, Type::Int)),
+        BinaryOps::Lt => return Ok(ConstValue::Int((l < r) as i64, Type::Int)),
+        BinaryOps::Le => return Ok(ConstValue::Int((l <= r) as i64, Type::Int)),
+        BinaryOps::Assign => return Err(ConstError::NotConstant("Assignment in constant expression".to_string())),
+    };
+    // the operands of an unsigned operation are wider than 64 bits here, and wrap around
+    // to the width of ty
+    if ty.is_unsigned() {
+        return Ok(ConstValue::Int(normalize(val as i64, ty), ty.clone()));
+    }
+    fit(val, ty)
+}
+
+pub fn float_binary_op(lhs: f64, rhs: f64, ty: &Type, op: &BinaryOps) -> ConstResult {
+    let val = match op {
+        BinaryOps::Add => lhs + rhs,
+        BinaryOps::Sub => lhs - rhs,
+        BinaryOps::Mul => lhs * rhs,
+        BinaryOps::Div => lhs / rhs,
+        BinaryOps::Eq => return Ok(ConstValue::Int((lhs == rhs) as i64, Type::Int)),
+        BinaryOps::Ne => return Ok(ConstValue::Int((lhs != rhs) as i64, Type::Int)),
+        BinaryOps::Lt => return Ok(ConstValue::Int((lhs < rhs) as i64, Type::Int)),
+        BinaryOps::Le => return Ok(ConstValue::Int((lhs <= rhs) as i64, Type::Int)),
+        BinaryOps::Assign => return Err(ConstError::NotConstant("Assignment in constant expression".to_string())),
+    };
+    Ok(float_of(val, ty))
+}
+
+pub fn negate(val: ConstValue) -> ConstResult {
+    match val {
+        ConstValue::Int(val, ty) if ty.is_unsigned() => Ok(ConstValue::Int(normalize(val.wrapping_neg(), &ty), ty)),
+        ConstValue::Int(val, ty) => fit(-(val as i128), &ty),
+        ConstValue::Float(val, ty) => Ok(ConstValue::Float(-val, ty)),
+        ConstValue::Addr(_) => Err(ConstError::NotConstant("Negation of an address is not a constant".to_string())),
+    }
+}
+
+// a floating value rounded to the precision of ty
+fn float_of(val: f64, ty: &Type) -> ConstValue {
+    match ty {
+        Type::Float => ConstValue::Float(val as f32 as f64, ty.clone()),
+        _ => ConstValue::Float(val, ty.clone()),
+    }
+}
+
+// the conversion of a constant to an arithmetic type, or of an integer to a pointer
+pub fn cast(val: ConstValue, ty: &Type) -> ConstResult {
+    let ty = ty.strip_qual();
+    match val {
+        ConstValue::Int(val, from) if ty.is_flonum() => Ok(float_of(widen(val, &from) as f64, ty)),
+        ConstValue::Int(val, _) if ty.is_integer() || ty.is_ptr() => Ok(ConstValue::Int(normalize(val, ty), ty.clone())),
+        ConstValue::Float(val, _) if ty.is_bool() => Ok(ConstValue::Int((val != 0.0) as i64, ty.clone())),
+        ConstValue::Float(val, from) if ty.is_integer() => {
+            // the value is truncated toward zero, and must fit in the integer type
+            let bits = ty.size() * 8;
+            let (min, max) = if ty.is_unsigned() {
+                (0.0, 2f64.powi(bits))
+            } else {
+                (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1))
+            };
+            let val = val.trunc();
+            if val.is_nan() || val < min || max <= val {
+                return Err(ConstError::Invalid(format!("Conversion of {} from {} to {} overflows", val, from, ty)));
+            }
+            Ok(ConstValue::Int(normalize(val as i128 as i64, ty), ty.clone()))
+        }
+        ConstValue::Float(val, _) if ty.is_flonum() => Ok(float_of(val, ty)),
+        ConstValue::Float(..) => Err(ConstError::Invalid(format!("Cannot convert a floating constant to {}", ty))),
+        ConstValue::Int(..) => Err(ConstError::Invalid(format!("Cannot convert an integer constant to {}", ty))),
+        ConstValue::Addr(_) => Err(ConstError::NotConstant(format!("Address cannot be converted to {} in a constant expression", ty))),
+    }
+}
+
+// the value of an integer constant expression which is not checked by sema
+pub fn eval_int_const(ast: &AST) -> Result<i64, ConstError> {
+    eval_unchecked(ast)?.to_int()
+}
+
+fn eval_unchecked(ast: &AST) -> ConstResult {
+    match ast {
+        AST::Int(val, ty) => Ok(ConstValue::Int(normalize(*val, ty), ty.clone())),
+        AST::Float(val, ty) => Ok(ConstValue::Float(*val, ty.clone())),
+        AST::Cast(expr, ty) if ty.is_arith() => cast(eval_unchecked(expr)?, ty),
+        AST::UnaryOp(expr, op @ (UnaryOps::Plus | UnaryOps::Minus)) => {
+            let val = eval_unchecked(expr)?;
+            let val = cast(val.clone(), &value_type(&val).promoted())?;
+            match op {
+                UnaryOps::Minus => negate(val),
+                _ => Ok(val),
+            }
+        }
+        AST::BinaryOp(lhs, rhs, op) => {
+            let (lhs, rhs) = (eval_unchecked(lhs)?, eval_unchecked(rhs)?);
+            let ty = types::usual_arith_conv(&value_type(&lhs), &value_type(&rhs));
+            match (cast(lhs, &ty)?, cast(rhs, &ty)?) {
+                (ConstValue::Int(lhs, _), ConstValue::Int(rhs, _)) => int_binary_op(lhs, rhs, &ty, op),
+                (ConstValue::Float(lhs, _), ConstValue::Float(rhs, _)) => float_binary_op(lhs, rhs, &ty, op),
+                _ => unreachable!(),
+            }
+        }
+        AST::Unevaluated(query, operand) => {
+            let ty = operand_type(operand, query)?;
+            match query {
+                TypeQuery::SizeOf => Ok(ConstValue::Int(ty.size() as i64, Type::ULong)),
+                TypeQuery::AlignOf => Ok(ConstValue::Int(ty.align() as i64, Type::ULong)),
+                TypeQuery::Generic(assocs, default) => {
+                    // a string literal is converted to a pointer to its first element
+                    let ty = match ty {
+                        Type::Array(elem_ty, _) => Type::Ptr(elem_ty),
+                        ty => ty,
+                    };
+                    let selected = assocs
+                        .iter()
+                        .find(|(assoc_ty, _)| sema::is_compatible(&ty, assoc_ty))
+                        .map(|(_, expr)| expr)
+                        .or(default.as_deref());
+                    match selected {
+                        Some(expr) => eval_unchecked(expr),
+                        None => Err(ConstError::Invalid(format!("_Generic has no association compatible with {}", ty))),
+                    }
+                }
+            }
+        }
+        AST::Load(lvalue) => match &**lvalue {
+            AST::Variable(name) => Err(ConstError::NotConstant(format!("{} cannot be used in a constant expression", name))),
+            _ => Err(ConstError::NotConstant("Reading an object is not a constant expression".to_string())),
+        },
+        AST::FuncCall(..) => Err(ConstError::NotConstant("Function call in a constant expression".to_string())),
+        _ => Err(ConstError::NotConstant("Expression is not a constant expression".to_string())),
+    }
+}
+
+fn value_type(val: &ConstValue) -> Type {
+    match val {
+        ConstValue::Int(_, ty) | ConstValue::Float(_, ty) => ty.clone(),
+        ConstValue::Addr(_) => unreachable!(),
+    }
+}
+
+// the type of the operand of sizeof, _Alignof or _Generic, which is known before semantic
+// analysis only for a constant or a string literal
+fn operand_type(ast: &AST, query: &TypeQuery) -> Result<Type, ConstError> {
+    if let AST::Load(lvalue) = ast {
+        if let AST::Str(s) = &**lvalue {
+            return Ok(Type::Array(Box::new(Type::Char), s.len() as i32 + 1));
+        }
+    }
+    match eval_unchecked(ast) {
+        Ok(val) => Ok(value_type(&val)),
+        Err(_) => {
+            let name = match query {
+                TypeQuery::SizeOf => "sizeof",
+                TypeQuery::AlignOf => "_Alignof",
+                TypeQuery::Generic(..) => "_Generic",
+            };
+            Err(ConstError::NotConstant(format!(
+                "Operand of {} in this constant expression must be a constant or a string literal",
+                name
+            )))
+        }
+    }
+}
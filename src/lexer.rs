@@ -11,6 +11,7 @@ pub enum TokenKind {
     Symbol,
     Keyword,
     Ident,
+    Str, // string literal, whose val holds a byte in each char
     NewLine, // not used
     Eof,
 }
@@ -41,6 +42,10 @@ impl Token {
         };
     }
 
+    pub fn is_str(&self) -> bool {
+        matches!(self.kind, TokenKind::Str)
+    }
+
     pub fn is_num(&self) -> bool {
         return match self.kind {
             TokenKind::IntNum | TokenKind::FloatNum => true,
@@ -52,6 +57,8 @@ impl Token {
 pub struct Lexer<'a> {
     cur_line: u32,
     filepath: String,
+    has_stdbool: bool, // whether <stdbool.h> is included
+    has_stdarg: bool,  // whether <stdarg.h> is included
     peek: iter::Peekable<str::Chars<'a>>,
     peek_pos: usize,
 }
@@ -88,6 +95,8 @@ impl<'a> Lexer<'a> {
         Lexer {
             cur_line: 0,
             filepath: path,
+            has_stdbool: false,
+            has_stdarg: false,
             peek: input.chars().peekable(),
             peek_pos: 0,
         }
@@ -123,10 +132,10 @@ impl<'a> Lexer<'a> {
 
     pub fn read_symbol(&mut self) -> Token {
         // multicharacter symbols
-        let ops = vec!["==", "!=", "<=", ">="];
+        let ops = vec!["...", "==", "!=", "<=", ">=", "->"];
         for op in ops {
             if self.starts_with(op) {
-                self.advance_by(2);
+                self.advance_by(op.len());
                 return Token {
                     kind: TokenKind::Symbol,
                     val: op.to_string(),
@@ -158,15 +167,41 @@ impl<'a> Lexer<'a> {
         loop {
             match self.peek.peek() {
                 Some(&c) => match c {
-                    'a'..='z' | 'A'..='Z' | '0'..='9' => string.push(c),
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => string.push(c),
                     _ => break,
                 },
                 _ => break,
             }
             self.peek_next();
         }
+        // macros defined by <stdbool.h>
+        if self.has_stdbool {
+            match string.as_str() {
+                "bool" => string = "_Bool".to_string(),
+                "true" | "false" => {
+                    return Token {
+                        kind: TokenKind::IntNum,
+                        val: if string == "true" { "1" } else { "0" }.to_string(),
+                        line: self.cur_line,
+                    };
+                }
+                _ => (),
+            }
+        }
+        // macros defined by <stdarg.h>, which refer to the builtins of the compiler
+        if self.has_stdarg {
+            match string.as_str() {
+                "va_list" | "va_start" | "va_arg" | "va_end" | "va_copy" => {
+                    string = format!("__builtin_{}", string);
+                }
+                _ => (),
+            }
+        }
         let tk = match string.as_str() {
-            "sizeof" | "int" | "if" | "else" | "for" | "while" => TokenKind::Keyword,
+            "sizeof" | "_Alignof" | "_Alignas" | "void" | "_Bool" | "char" | "short" | "int" | "long" | "signed" | "unsigned" | "float"
+            | "double" | "struct" | "union" | "enum" | "typedef" | "extern" | "static" | "auto" | "register"
+            | "_Thread_local" | "const" | "volatile" | "restrict" | "_Atomic" | "_Static_assert" | "_Generic"
+            | "if" | "else" | "for" | "while" => TokenKind::Keyword,
             _ => TokenKind::Ident,
         };
         Token {
@@ -187,6 +222,19 @@ impl<'a> Lexer<'a> {
                         s.push(c);
                         is_float = true;
                     }
+                    // exponent part (e.g. 1e10, 2.5E-3)
+                    'e' | 'E' => {
+                        s.push(c);
+                        is_float = true;
+                        self.peek_next();
+                        if let Some(&sign) = self.peek.peek() {
+                            if sign == '+' || sign == '-' {
+                                s.push(sign);
+                                self.peek_next();
+                            }
+                        }
+                        continue;
+                    }
                     _ => break,
                 },
                 _ => break,
@@ -194,12 +242,27 @@ impl<'a> Lexer<'a> {
             self.peek_next();
         }
         if is_float {
+            // floating suffix: f/F means float, l/L means long double
+            if let Some(&c) = self.peek.peek() {
+                if c == 'f' || c == 'F' || c == 'l' || c == 'L' {
+                    s.push(c);
+                    self.peek_next();
+                }
+            }
             Token {
                 kind: TokenKind::FloatNum,
                 val: s,
                 line: self.cur_line,
             }
         } else {
+            // integer suffix: any combination of u/U and l/L/ll/LL
+            while let Some(&c) = self.peek.peek() {
+                if c != 'u' && c != 'U' && c != 'l' && c != 'L' {
+                    break;
+                }
+                s.push(c);
+                self.peek_next();
+            }
             Token {
                 kind: TokenKind::IntNum,
                 val: s,
@@ -208,6 +271,82 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // string literal with its escape sequences decoded
+    pub fn read_str_literal(&mut self) -> Token {
+        self.peek_next();
+        let mut s = String::new();
+        loop {
+            match self.peek_next() {
+                Some('"') => break,
+                Some('\\') => s.push(self.read_escape() as char),
+                Some('\n') | None => panic!("Missing terminating \" character"),
+                // multibyte characters are stored byte by byte
+                Some(c) => {
+                    let mut buf = [0; 4];
+                    for b in c.encode_utf8(&mut buf).bytes() {
+                        s.push(b as char);
+                    }
+                }
+            }
+        }
+        Token {
+            kind: TokenKind::Str,
+            val: s,
+            line: self.cur_line,
+        }
+    }
+
+    fn read_escape(&mut self) -> u8 {
+        let c = match self.peek_next() {
+            Some(c) => c,
+            None => panic!("Incomplete escape sequence"),
+        };
+        match c {
+            'a' => 7,
+            'b' => 8,
+            't' => b'\t',
+            'n' => b'\n',
+            'v' => 11,
+            'f' => 12,
+            'r' => b'\r',
+            // GNU extension: ESC
+            'e' => 27,
+            // up to three octal digits
+            '0'..='7' => {
+                let mut n = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match self.peek.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => n = n * 8 + d,
+                        None => break,
+                    }
+                    self.peek_next();
+                }
+                if n > 0xff {
+                    panic!("Octal escape sequence out of range");
+                }
+                n as u8
+            }
+            'x' => {
+                let mut n: u32 = 0;
+                let mut digits = 0;
+                while let Some(d) = self.peek.peek().and_then(|c| c.to_digit(16)) {
+                    n = n.saturating_mul(16).saturating_add(d);
+                    digits += 1;
+                    self.peek_next();
+                }
+                if digits == 0 {
+                    panic!("\\x used with no following hex digits");
+                }
+                if n > 0xff {
+                    panic!("Hex escape sequence out of range");
+                }
+                n as u8
+            }
+            // \\, \', \" and \? stand for the character itself
+            _ => c as u8,
+        }
+    }
+
     fn read_directive(&mut self) {
         let dir_string = self.read_token().unwrap().val;
         if dir_string == "include" {
@@ -225,16 +364,26 @@ impl<'a> Lexer<'a> {
         }
         self.skip_token(">");
         println!("include: {}", filename);
+        // builtin headers
+        if filename == "stdbool.h" {
+            self.has_stdbool = true;
+        }
+        if filename == "stdarg.h" {
+            self.has_stdarg = true;
+        }
         // TODO: implement #include here
     }
 
     pub fn read_token(&mut self) -> Option<Token> {
         match self.peek.peek() {
             Some(&c) => match c {
-                'a'..='z' | 'A'..='Z' => Some(self.read_string_token()),
+                'a'..='z' | 'A'..='Z' | '_' => Some(self.read_string_token()),
+                // floating literal without integer part (e.g. .5)
+                '.' if matches!(self.peek.clone().nth(1), Some('0'..='9')) => Some(self.read_num()),
                 '+' | '-' | '*' | '/' | '(' | ')' | '=' | '<' | '>' | '!' | '&' | ',' | ';'
-                | '{' | '}' | '[' | ']' | '.' => Some(self.read_symbol()),
+                | '{' | '}' | '[' | ']' | '.' | ':' => Some(self.read_symbol()),
                 '0'..='9' => Some(self.read_num()),
+                '"' => Some(self.read_str_literal()),
                 ' ' | '\t' | '\r' => {
                     self.peek_next();
                     self.read_token()
@@ -1,7 +1,11 @@
+pub mod abi;
 pub mod codegen;
+pub mod const_eval;
 pub mod error;
 pub mod lexer;
 pub mod node;
 pub mod parser;
+pub mod sema;
 pub mod types;
 pub mod version;
+pub mod warning;
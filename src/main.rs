@@ -2,6 +2,7 @@ extern crate ironcc;
 use ironcc::codegen;
 use ironcc::lexer;
 use ironcc::parser;
+use ironcc::sema;
 use ironcc::version;
 
 use std::env;
@@ -9,11 +10,17 @@ use std::env;
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        version::show_version();
-        version::show_usage();
-    } else {
-        let filepath = args[1].clone();
+    let mut options = sema::Options::default();
+    let mut filepath = None;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            _ if options.warnings.parse_option(arg) => (),
+            _ if arg.starts_with('-') => panic!("Unknown option {}", arg),
+            _ => filepath = Some(arg.clone()),
+        }
+    }
+
+    if let Some(filepath) = filepath {
         // test
         // tokenize
         let tokens = lexer::run(filepath.clone());
@@ -25,6 +32,8 @@ fn main() {
         for node in &nodes {
             //println!("{:?}", node);
         }
+        // semantic analysis
+        let nodes = sema::run(nodes, options);
 
         unsafe {
             let mut codegen = codegen::Codegen::new(filepath.clone().as_str());
@@ -32,7 +41,8 @@ fn main() {
             codegen.dump_module();
             codegen.write_llvm_bc();
         }
-
-        
+    } else {
+        version::show_version();
+        version::show_usage();
     }
 }
@@ -1,25 +1,69 @@
-use crate::types::Type;
+use crate::types::{BitField, Type};
 
 #[derive(Debug, Clone)]
 pub enum AST {
-    Int(i32),
-    Float(f64),
+    Int(i64, Type),   // value, int/long/unsigned ...
+    Float(f64, Type), // value, float/double/long double
     BinaryOp(Box<AST>, Box<AST>, BinaryOps),
     UnaryOp(Box<AST>, UnaryOps),
     Load(Box<AST>),
+    Cast(Box<AST>, Type), // expr, type converted to
     Variable(String),
-    VariableDecl(Type, String, Option<Box<AST>>), // type, name, init val
+    Str(Vec<u8>), // string literal without the terminating null character
+    Member(Box<AST>, String), // struct or union, member name
+    Index(Box<AST>, Box<AST>), // pointer, index
+    InitList(Vec<InitElem>),   // brace-enclosed initializer, before semantic analysis
+    Initializer(Vec<InitLeaf>), // initializer of an aggregate, resolved by semantic analysis
+    CompoundLiteral(Type, Box<AST>), // type, initializer
+    Unevaluated(TypeQuery, Box<AST>), // what is computed from the type of the operand, operand which is not evaluated
+    VariableDecl(Type, String, Option<Box<AST>>, VarAttr), // type, name, init val, attributes
+    // hidden variable, length of an array which the parser could not fold (e.g. int a[n]),
+    // before semantic analysis
+    ArrayLen(String, Box<AST>),
+    StaticAssert(Box<AST>, Option<Vec<u8>>), // condition, message, before semantic analysis
     Return(Option<Box<AST>>),
     //ExprStmt(Box<AST>),
     Block(Vec<AST>),
     If(Box<AST>, Box<AST>, Box<AST>),            // cond, then, els
     For(Box<AST>, Box<AST>, Box<AST>, Box<AST>), // init, cond, step, body
     While(Box<AST>, Box<AST>),                   // cond, body
-    FuncCall(String, Vec<AST>),                  // func-name, args
-    FuncDef(Box<Type>, String, Box<AST>), // functype, func name, param types, param names, locals, body
+    FuncCall(Box<AST>, Vec<AST>),                // callee, args
+    VaStart(Box<AST>),                           // va_list
+    VaArg(Box<AST>, Type),                       // va_list, type of the argument
+    VaEnd(Box<AST>),                             // va_list
+    VaCopy(Box<AST>, Box<AST>),                  // dest, src
+    FuncDef(Box<Type>, String, Box<AST>, VarAttr), // functype, func name, body, attributes
+    FuncDecl(Box<Type>, String, VarAttr),          // functype, func name, attributes
     Nil, // forのcond、ifのelse、expr-stmtのexprにおいて式や文などが存在しないときに用いる
 }
 
+// the result of sizeof, _Alignof or _Generic, which depends only on the type of the operand
+#[derive(Debug, Clone)]
+pub enum TypeQuery {
+    SizeOf,
+    AlignOf,
+    Generic(Vec<(Type, AST)>, Option<Box<AST>>), // associations, default association
+}
+
+// an element of an initializer list: the designators before `=`, and the initializer
+pub type InitElem = (Vec<Designator>, AST);
+
+#[derive(Debug, Clone)]
+pub enum Designator {
+    Index(i64, i64), // [first ... last], or [index] where both are the same
+    Member(String),  // .name
+}
+
+// a scalar (or a struct initialized by an expression, or a char array initialized by
+// AST::Str) at a byte offset in the object; the bytes not covered by any leaf are zero
+#[derive(Debug, Clone)]
+pub struct InitLeaf {
+    pub offset: i32,
+    pub ty: Type,
+    pub bit_field: Option<BitField>,
+    pub expr: AST,
+}
+
 #[derive(Debug, Clone)]
 pub enum BinaryOps {
     Add,
@@ -39,29 +83,23 @@ pub enum UnaryOps {
     Minus, // -
     Addr,  // &
     Deref, // *
-    Sizeof,
 }
 
-impl AST {
-    pub fn eval_const_expr(&self) -> i32 {
-        match &self {
-            AST::Int(n) => *n,
-            AST::BinaryOp(l, r, op) => {
-                let l = l.eval_const_expr();
-                let r = r.eval_const_expr();
-                match op {
-                    &BinaryOps::Add => l + r,
-                    &BinaryOps::Sub => l - r,
-                    &BinaryOps::Mul => l * r,
-                    &BinaryOps::Div => l / r,
-                    &BinaryOps::Eq => (l == r) as i32,
-                    &BinaryOps::Ne => (l != r) as i32,
-                    &BinaryOps::Lt => (l < r) as i32,
-                    &BinaryOps::Le => (l <= r) as i32,
-                    _ => panic!("Unknown operator"),
-                }
-            }
-            _ => panic!("Expected constant expression"),
-        }
+// storage-class specifiers and alignment specifiers of a declaration
+#[derive(Debug, Clone, Default)]
+pub struct VarAttr {
+    pub is_typedef: bool,
+    pub is_extern: bool,
+    pub is_static: bool,
+    pub is_auto: bool,
+    pub is_register: bool,
+    pub is_thread_local: bool,
+    pub align: Option<i32>, // _Alignas
+}
+
+impl VarAttr {
+    // _Thread_local is the only one which can be combined with another (static or extern)
+    pub fn has_storage_class(&self) -> bool {
+        self.is_typedef || self.is_extern || self.is_static || self.is_auto || self.is_register
     }
 }
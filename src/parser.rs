@@ -1,11 +1,19 @@
+use crate::const_eval;
+use crate::const_eval::ConstError;
 use crate::lexer;
 use crate::node;
-use crate::node::UnaryOps;
-use crate::types::Type;
+use crate::node::{TypeQuery, UnaryOps};
+use crate::sema;
+use crate::types;
+use crate::types::{Qualifiers, Type};
 
 use lexer::Token;
 use lexer::TokenKind;
-use node::{BinaryOps, AST};
+use node::{BinaryOps, Designator, InitElem, VarAttr, AST};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use types::{MemberDecl, StructDef};
 
 pub fn run(filepath: String, tokens: Vec<Token>) -> Vec<AST> {
     let mut parser = Parser::new(filepath, tokens);
@@ -13,19 +21,136 @@ pub fn run(filepath: String, tokens: Vec<Token>) -> Vec<AST> {
     ast
 }
 
+// sizeof and _Alignof of a type are folded into constants of type size_t in the parser,
+// so that they can be used in the constant expressions it folds; those of an expression
+// are resolved by sema, which knows its type
+fn sizeof(ty: &Type) -> i32 {
+    if !ty.is_complete() || matches!(ty, Type::Func(..)) {
+        panic!("Invalid application of sizeof to type {}", ty);
+    }
+    ty.size()
+}
+
+// split a floating literal into its digits and the type given by its suffix
+fn split_float_suffix(s: &str) -> (&str, Type) {
+    if s.ends_with('f') || s.ends_with('F') {
+        (&s[..s.len() - 1], Type::Float)
+    } else if s.ends_with('l') || s.ends_with('L') {
+        (&s[..s.len() - 1], Type::LongDouble)
+    } else {
+        (s, Type::Double)
+    }
+}
+
+// parse an integer literal and decide its type from the suffix and the value (C11 6.4.4.1)
+fn parse_int_literal(s: &str) -> (i64, Type) {
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, suffix) = s.split_at(digits_end);
+    let n = digits.parse::<u64>().expect("Integer literal is too large");
+    let suffix = suffix.to_lowercase();
+    let is_unsigned = suffix.contains('u');
+    let long_cnt = suffix.matches('l').count();
+    let ty = if is_unsigned {
+        if long_cnt == 0 && n <= u32::MAX as u64 {
+            Type::UInt
+        } else if long_cnt < 2 {
+            Type::ULong
+        } else {
+            Type::ULongLong
+        }
+    } else if long_cnt == 0 && n <= i32::MAX as u64 {
+        Type::Int
+    } else if long_cnt < 2 && n <= i64::MAX as u64 {
+        Type::Long
+    } else if n <= i64::MAX as u64 {
+        Type::LongLong
+    } else if long_cnt < 2 {
+        Type::ULong
+    } else {
+        Type::ULongLong
+    };
+    (n as i64, ty)
+}
+
+// parameters of array and function types are adjusted to pointers
+fn adjust_param_type(ty: Type) -> Type {
+    match ty {
+        Type::Array(elem_ty, _) | Type::Vla(elem_ty, _) => Type::Ptr(elem_ty),
+        Type::Func(..) => Type::Ptr(Box::new(ty)),
+        _ => ty,
+    }
+}
+
+// the type read from the identifier list of an old-style definition, which has
+// parameter names but no parameter types yet
+fn is_identifier_list(ty: &Type) -> bool {
+    matches!(ty, Type::Func(_, param_types, param_names, _) if param_types.is_empty() && !param_names.is_empty())
+}
+
+const STORAGE_CLASSES: [&str; 6] = ["typedef", "extern", "static", "auto", "register", "_Thread_local"];
+
+// what an ordinary identifier refers to in a scope
+#[derive(Debug, Clone)]
+enum VarScope {
+    Var,
+    EnumConst(i64, Type), // value, type
+    Typedef(Type),
+}
+
 pub struct Parser {
     filepath: String,
     pos: usize,
     tokens: Vec<Token>,
+    var_scopes: Vec<HashMap<String, VarScope>>, // variables, enumerators and typedef names
+    tag_scopes: Vec<HashMap<String, Type>>,     // struct, union and enum tags
+    struct_cnt: usize,
+    // lengths of arrays which could not be folded (AST::ArrayLen), held by hidden variables
+    // and emitted before the declaration whose declarator they appear in
+    vla_lens: Vec<AST>,
+    vla_cnt: usize,
+    // those in the parameters of the last function declarator, used if it starts a definition
+    param_vla_lens: Vec<AST>,
 }
 
 impl Parser {
     pub fn new(path: String, tok: Vec<Token>) -> Parser {
-        Parser {
+        let mut parser = Parser {
             filepath: path,
             pos: 0,
             tokens: tok,
+            var_scopes: vec![HashMap::new()],
+            tag_scopes: vec![HashMap::new()],
+            struct_cnt: 0,
+            vla_lens: Vec::new(),
+            vla_cnt: 0,
+            param_vla_lens: Vec::new(),
+        };
+        parser.declare_builtin_types();
+        parser
+    }
+
+    // va_list of the SysV x86-64 ABI, an array of one struct so that it is passed by reference:
+    //   typedef struct __va_list_tag {
+    //       unsigned int gp_offset;
+    //       unsigned int fp_offset;
+    //       void *overflow_arg_area;
+    //       void *reg_save_area;
+    //   } __builtin_va_list[1];
+    fn declare_builtin_types(&mut self) {
+        let tag = self.new_struct_type(Some("__va_list_tag".to_string()), false);
+        if let Type::Struct(st) = &tag {
+            let void_ptr = Type::Ptr(Box::new(Type::Void));
+            st.borrow_mut().set_members(vec![
+                (Some("gp_offset".to_string()), Type::UInt, None, None),
+                (Some("fp_offset".to_string()), Type::UInt, None, None),
+                (Some("overflow_arg_area".to_string()), void_ptr.clone(), None, None),
+                (Some("reg_save_area".to_string()), void_ptr, None, None),
+            ]);
         }
+        self.var_scopes[0].insert(
+            "__builtin_va_list".to_string(),
+            VarScope::Typedef(Type::Array(Box::new(tag), 1)),
+        );
     }
 
     pub fn get_filepath(&self) -> String {
@@ -79,22 +204,147 @@ impl Parser {
     fn read_program(&mut self) -> Vec<AST> {
         let mut ret = Vec::new();
         while !self.cur().is_eof() {
-            ret.push(self.read_top_level());
+            ret.append(&mut self.read_top_level());
         }
         ret
     }
 
-    fn read_top_level(&mut self) -> AST {
-        self.read_func_def()
+    // a function definition, or a declaration of global variables and functions
+    fn read_top_level(&mut self) -> Vec<AST> {
+        if self.consume("_Static_assert") {
+            return vec![self.read_static_assert()];
+        }
+        let (declspec, attr) = self.read_declspec_attr();
+        // declaration of struct or union tags only
+        if self.consume(";") {
+            return Vec::new();
+        }
+        // sema requires the lengths of arrays at file scope to be constant
+        if attr.is_typedef {
+            self.read_typedef(declspec, &attr);
+            return self.vla_lens.drain(..).collect();
+        }
+        let (ty, name) = self.read_declarator(declspec.clone());
+        let mut decls: Vec<AST> = self.vla_lens.drain(..).collect();
+        if let Type::Func(..) = ty {
+            // declarations of the parameters of an old-style definition come before the body
+            if self.cur().matches("{") || is_identifier_list(&ty) && !self.cur().matches(";") && !self.cur().matches(",") {
+                if attr.align.is_some() {
+                    panic!("_Alignas cannot be specified for function {}", name);
+                }
+                decls.push(self.read_func_def(ty, name, attr));
+                return decls;
+            }
+        }
+        decls.push(self.read_init_declarator(ty, name, &attr));
+        while self.consume(",") {
+            let (ty, name) = self.read_declarator(declspec.clone());
+            decls.append(&mut self.vla_lens);
+            decls.push(self.read_init_declarator(ty, name, &attr));
+        }
+        self.consume_expected(";");
+        decls
+    }
+
+    // typedef names are declared in the current scope, and generate no code
+    fn read_typedef(&mut self, declspec: Type, attr: &VarAttr) {
+        if attr.align.is_some() {
+            panic!("_Alignas cannot be specified for a typedef");
+        }
+        loop {
+            let (ty, name) = self.read_declarator(declspec.clone());
+            let typedef = VarScope::Typedef(ty);
+            self.check_redeclaration(&name, &typedef);
+            self.var_scopes.last_mut().unwrap().insert(name, typedef);
+            if !self.consume(",") {
+                break;
+            }
+        }
+        self.consume_expected(";");
     }
 
-    fn read_func_def(&mut self) -> AST {
-        let func_ty = self.read_declspec();
-        let (func_ty, func_name) = self.read_declarator(func_ty);
+    fn read_func_def(&mut self, func_ty: Type, func_name: String, attr: VarAttr) -> AST {
+        // the lengths of variable length array parameters are evaluated on entry to the function
+        let vla_lens = std::mem::take(&mut self.param_vla_lens);
+        let (func_ty, mut prologue) = match func_ty {
+            Type::Func(ret_ty, _, param_names, _) if is_identifier_list(&func_ty) => {
+                let (func_ty, mut prologue) = self.read_old_style_params(ret_ty, param_names);
+                prologue.append(&mut self.vla_lens);
+                (func_ty, prologue)
+            }
+            // a definition with an empty parameter list takes no parameters
+            Type::Func(ret_ty, param_types, param_names, true) if param_types.is_empty() => {
+                (Type::Func(ret_ty, param_types, param_names, false), Vec::new())
+            }
+            func_ty => (func_ty, Vec::new()),
+        };
+        prologue.extend(vla_lens);
+        self.declare_var(&func_name);
         self.consume_expected("{");
-        let body = self.read_compound_stmt();
+        // parameters hide enumerators of the same name in the body
+        self.enter_scope();
+        if let Type::Func(_, _, param_names, _) = &func_ty {
+            for name in param_names {
+                self.declare_var(name);
+            }
+        }
+        for decl in &prologue {
+            if let AST::VariableDecl(_, name, ..) = decl {
+                self.declare_var(name);
+            }
+        }
+        let body = match self.read_compound_stmt() {
+            AST::Block(block) => AST::Block(prologue.into_iter().chain(block).collect()),
+            body => body,
+        };
+        self.leave_scope();
 
-        return AST::FuncDef(Box::new(func_ty), func_name, Box::new(body));
+        return AST::FuncDef(Box::new(func_ty), func_name, Box::new(body), attr);
+    }
+
+    // the parameter declarations of an old-style definition (e.g. int f(a, b) char a; { ... }),
+    // where undeclared parameters are int. callers pass the arguments with the default argument
+    // promotions, so the function takes the promoted types, and a parameter whose type changes
+    // by the promotion is received as "name.promoted" and converted to a local variable
+    fn read_old_style_params(&mut self, ret_ty: Box<Type>, names: Vec<String>) -> (Type, Vec<AST>) {
+        let mut declared: HashMap<String, Type> = HashMap::new();
+        while !self.cur().matches("{") {
+            let (declspec, attr) = self.read_declspec_attr();
+            if attr.has_storage_class() && !attr.is_register || attr.is_thread_local {
+                panic!("Invalid storage class for a parameter");
+            }
+            loop {
+                let (ty, name) = self.read_declarator(declspec.clone());
+                if !names.contains(&name) {
+                    panic!("Declaration for parameter {} but no such parameter", name);
+                }
+                if declared.insert(name.clone(), adjust_param_type(ty)).is_some() {
+                    panic!("Redefinition of parameter {}", name);
+                }
+                if !self.consume(",") {
+                    break;
+                }
+            }
+            self.consume_expected(";");
+        }
+        let mut param_types = Vec::new();
+        let mut param_names = Vec::new();
+        let mut prologue = Vec::new();
+        for name in names {
+            let ty = declared.remove(&name).unwrap_or(Type::Int);
+            let promoted_ty = ty.default_promoted();
+            if promoted_ty == ty.unqualified() {
+                param_types.push(ty);
+                param_names.push(name);
+                continue;
+            }
+            let promoted_name = format!("{}.promoted", name);
+            let init = AST::Load(Box::new(AST::Variable(promoted_name.clone())));
+            prologue.push(AST::VariableDecl(ty, name, Some(Box::new(init)), VarAttr::default()));
+            param_types.push(promoted_ty);
+            param_names.push(promoted_name);
+        }
+        (Type::Func(ret_ty, param_types, param_names, false), prologue)
     }
 
     fn read_stmt(&mut self) -> AST {
@@ -119,7 +369,13 @@ impl Parser {
             return AST::If(Box::new(cond), Box::new(then), Box::new(els));
         } else if self.consume("for") {
             self.consume_expected("(");
-            let init = self.read_expr_stmt();
+            // a variable declared in the init clause is visible only in the loop
+            self.enter_scope();
+            let init = if self.is_typename() {
+                AST::Block(self.read_declaration())
+            } else {
+                self.read_expr_stmt()
+            };
             let mut cond = AST::Nil;
             if !self.consume(";") {
                 cond = self.read_expr();
@@ -131,6 +387,7 @@ impl Parser {
                 self.consume(")");
             }
             let body = self.read_stmt();
+            self.leave_scope();
             return AST::For(
                 Box::new(init),
                 Box::new(cond),
@@ -152,94 +409,840 @@ impl Parser {
 
     fn read_compound_stmt(&mut self) -> AST {
         let mut v = Vec::new();
+        self.enter_scope();
         while !self.consume("}") {
-            let ast;
-            if self.cur().matches("int") {
-                ast = self.read_declaration();
+            if self.consume("_Static_assert") {
+                v.push(self.read_static_assert());
+            } else if self.is_typename() {
+                v.extend(self.read_declaration());
             } else {
-                ast = self.read_stmt();
+                v.push(self.read_stmt());
             }
-            v.push(ast);
         }
+        self.leave_scope();
         AST::Block(v)
     }
 
-    fn read_declaration(&mut self) -> AST {
+    // _Static_assert(const-expr, "message"), which sema checks; the message is optional
+    fn read_static_assert(&mut self) -> AST {
+        self.consume_expected("(");
+        let cond = self.read_equality();
+        let mut msg = None;
+        if self.consume(",") {
+            if !self.cur().is_str() {
+                panic!("Expected string literal in _Static_assert");
+            }
+            msg = Some(self.read_str_bytes());
+        }
+        self.consume_expected(")");
+        self.consume_expected(";");
+        AST::StaticAssert(Box::new(cond), msg)
+    }
+
+    // an integer constant expression which the parser needs, e.g. the value of an enumerator
+    fn read_const_int(&mut self) -> i64 {
+        let expr = self.read_equality();
+        const_eval::eval_int_const(&expr).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn enter_scope(&mut self) {
+        self.var_scopes.push(HashMap::new());
+        self.tag_scopes.push(HashMap::new());
+    }
+
+    fn leave_scope(&mut self) {
+        self.var_scopes.pop();
+        self.tag_scopes.pop();
+    }
+
+    fn declare_var(&mut self, name: &str) {
+        let var = VarScope::Var;
+        self.check_redeclaration(name, &var);
+        self.var_scopes.last_mut().unwrap().insert(name.to_string(), var);
+    }
+
+    // an identifier cannot denote two kinds of things (a variable, an enumerator or a typedef name)
+    // in the same scope; a typedef name may be redefined to the same type. redeclarations of
+    // variables are checked by sema
+    fn check_redeclaration(&self, name: &str, new: &VarScope) {
+        match (self.var_scopes.last().unwrap().get(name), new) {
+            (None, _) | (Some(VarScope::Var), VarScope::Var) => (),
+            (Some(VarScope::Typedef(prev_ty)), VarScope::Typedef(ty)) if prev_ty == ty => (),
+            _ => panic!("Redefinition of {}", name),
+        }
+    }
+
+    fn find_var(&self, name: &str) -> Option<VarScope> {
+        for scope in self.var_scopes.iter().rev() {
+            if let Some(var) = scope.get(name) {
+                return Some(var.clone());
+            }
+        }
+        None
+    }
+
+    fn read_declaration(&mut self) -> Vec<AST> {
         let mut decls = Vec::new();
-        let declspec = self.read_declspec();
-        
-        let (ty, name) = self.read_declarator(declspec.clone());
+        let (declspec, attr) = self.read_declspec_attr();
+        // declaration of struct or union tags only
+        if self.consume(";") {
+            return decls;
+        }
+        if attr.is_typedef {
+            self.read_typedef(declspec, &attr);
+            return self.vla_lens.drain(..).collect();
+        }
+
+        loop {
+            let (ty, name) = self.read_declarator(declspec.clone());
+            // the lengths of variable length arrays are evaluated before the object is allocated
+            decls.append(&mut self.vla_lens);
+            decls.push(self.read_init_declarator(ty, name, &attr));
+            if !self.consume(",") {
+                break;
+            }
+        }
+        self.consume_expected(";");
+        decls
+    }
+
+    // the initializer after a declarator; a declarator of function type declares a function
+    fn read_init_declarator(&mut self, ty: Type, name: String, attr: &VarAttr) -> AST {
+        self.declare_var(&name);
+        if let Type::Func(..) = ty {
+            if attr.align.is_some() {
+                panic!("_Alignas cannot be specified for function {}", name);
+            }
+            if self.cur().matches("=") {
+                panic!("Function {} is initialized like a variable", name);
+            }
+            if is_identifier_list(&ty) {
+                panic!("Parameter names without types in declaration of function {}", name);
+            }
+            return AST::FuncDecl(Box::new(ty), name, attr.clone());
+        }
         let mut init_val = None;
         if self.consume("=") {
-            init_val = Some(Box::new(self.read_expr()));
+            init_val = Some(Box::new(self.read_initializer()));
         }
-        decls.push(AST::VariableDecl(ty, name, init_val));
+        AST::VariableDecl(ty, name, init_val, attr.clone())
+    }
 
-        while self.consume(",") {
-            let (ty, name) = self.read_declarator(declspec.clone());
+    // an expression, or a brace-enclosed list of initializers with optional designators
+    fn read_initializer(&mut self) -> AST {
+        if !self.consume("{") {
+            return self.read_assign();
+        }
+        let mut elems = Vec::new();
+        // an empty initializer list zero-initializes the object as a GNU extension
+        while !self.consume("}") {
+            elems.push(self.read_init_elem());
+            // a trailing comma is allowed
+            if !self.consume(",") {
+                self.consume_expected("}");
+                break;
+            }
+        }
+        AST::InitList(elems)
+    }
 
-            let mut init_val = None;
-            if self.consume("=") {
-                init_val = Some(Box::new(self.read_expr()));
+    fn read_init_elem(&mut self) -> InitElem {
+        let mut designators = Vec::new();
+        loop {
+            if self.consume("[") {
+                let first = self.read_const_int();
+                // [first ... last] is a GNU extension
+                let last = if self.consume("...") {
+                    self.read_const_int()
+                } else {
+                    first
+                };
+                self.consume_expected("]");
+                if first < 0 {
+                    panic!("Array index in initializer is negative");
+                }
+                if last < first {
+                    panic!("Empty index range in initializer");
+                }
+                designators.push(Designator::Index(first, last));
+            } else if self.consume(".") {
+                designators.push(Designator::Member(self.read_ident()));
+            } else {
+                break;
             }
-            decls.push(AST::VariableDecl(ty, name, init_val));
         }
-        self.consume_expected(";");
-        AST::Block(decls)
+        if !designators.is_empty() {
+            self.consume_expected("=");
+        }
+        (designators, self.read_initializer())
+    }
+
+    // whether a declaration starts here
+    fn is_typename(&self) -> bool {
+        self.is_typename_at(self.pos)
+    }
+
+    fn is_typename_at(&self, pos: usize) -> bool {
+        match self.tokens.get(pos) {
+            Some(tok) => {
+                STORAGE_CLASSES.iter().any(|s| tok.matches(s))
+                    || self.is_type_keyword_token(tok)
+                    || tok.is_ident() && self.find_typedef(&tok.val).is_some()
+            }
+            None => false,
+        }
+    }
+
+    fn is_typedef_name(&self) -> bool {
+        self.cur().is_ident() && self.find_typedef(&self.cur().val).is_some()
     }
 
+    fn find_typedef(&self, name: &str) -> Option<Type> {
+        match self.find_var(name) {
+            Some(VarScope::Typedef(ty)) => Some(ty),
+            _ => None,
+        }
+    }
+
+    fn is_type_keyword(&self) -> bool {
+        self.is_type_keyword_token(&self.cur())
+    }
+
+    fn is_type_keyword_token(&self, tok: &Token) -> bool {
+        matches!(tok.kind, TokenKind::Keyword)
+            && matches!(
+                tok.val.as_str(),
+                "void"
+                    | "_Alignas"
+                    | "const"
+                    | "volatile"
+                    | "restrict"
+                    | "_Atomic"
+                    | "_Bool"
+                    | "struct"
+                    | "union"
+                    | "enum"
+                    | "char"
+                    | "short"
+                    | "int"
+                    | "long"
+                    | "signed"
+                    | "unsigned"
+                    | "float"
+                    | "double"
+            )
+    }
+
+    fn read_storage_class(&mut self, attr: &mut VarAttr) {
+        let tok = self.next();
+        if tok.matches("_Thread_local") {
+            if attr.is_thread_local {
+                panic!("Duplicate _Thread_local");
+            }
+            attr.is_thread_local = true;
+        } else {
+            if attr.has_storage_class() {
+                panic!("Multiple storage classes in declaration specifiers");
+            }
+            match tok.val.as_str() {
+                "typedef" => attr.is_typedef = true,
+                "extern" => attr.is_extern = true,
+                "static" => attr.is_static = true,
+                "auto" => attr.is_auto = true,
+                "register" => attr.is_register = true,
+                _ => unreachable!(),
+            }
+        }
+        if attr.is_thread_local && (attr.is_typedef || attr.is_auto || attr.is_register) {
+            panic!("_Thread_local cannot be used with typedef, auto or register");
+        }
+    }
+
+    // type qualifiers, which may be repeated; _Atomic followed by ( is a type specifier instead
+    fn read_qualifiers(&mut self) -> Qualifiers {
+        let mut quals = Qualifiers::default();
+        loop {
+            if self.consume("const") {
+                quals.is_const = true;
+            } else if self.consume("volatile") {
+                quals.is_volatile = true;
+            } else if self.consume("restrict") {
+                quals.is_restrict = true;
+            } else if self.cur().matches("_Atomic") && !self.peek().matches("(") {
+                self.next();
+                quals.is_atomic = true;
+            } else {
+                return quals;
+            }
+        }
+    }
+
+    // _Atomic(type-name)
+    fn read_atomic_type(&mut self) -> Type {
+        self.consume_expected("(");
+        let ty = self.read_typename();
+        self.consume_expected(")");
+        if matches!(ty, Type::Array(..) | Type::Func(..) | Type::Qual(..)) {
+            panic!("_Atomic cannot be applied to {}", ty);
+        }
+        ty.qualified(Qualifiers {
+            is_atomic: true,
+            ..Default::default()
+        })
+    }
+
+    // declaration specifiers where storage-class and alignment specifiers are not allowed
     fn read_declspec(&mut self) -> Type {
-        match self.next().val.as_str() {
-            "int" => return Type::Int,
+        let (ty, attr) = self.read_declspec_attr();
+        if attr.has_storage_class() || attr.is_thread_local {
+            panic!("Storage class specifier is not allowed here");
+        }
+        if attr.align.is_some() {
+            panic!("_Alignas is not allowed here");
+        }
+        ty
+    }
+
+    // _Alignas(type) or _Alignas(constant-expression)
+    fn read_alignas(&mut self) -> i32 {
+        self.consume_expected("(");
+        let align = if self.is_typename() {
+            self.read_typename().align()
+        } else {
+            let align = self.read_const_int();
+            if align < 0 || align & (align - 1) != 0 {
+                panic!("Requested alignment {} is not a power of 2", align);
+            }
+            align as i32
+        };
+        self.consume_expected(")");
+        align
+    }
+
+    fn read_declspec_attr(&mut self) -> (Type, VarAttr) {
+        let mut attr = VarAttr::default();
+        // struct, union and enum types, and types named by typedef
+        let mut named_ty = None;
+        let mut specs = Vec::new();
+        let mut quals = Qualifiers::default();
+        loop {
+            let qual = self.read_qualifiers();
+            if !qual.is_empty() {
+                quals = quals.union(&qual);
+                continue;
+            }
+            if STORAGE_CLASSES.iter().any(|s| self.cur().matches(s)) {
+                self.read_storage_class(&mut attr);
+                continue;
+            }
+            // the strictest alignment specifier applies; _Alignas(0) has no effect
+            if self.consume("_Alignas") {
+                let align = self.read_alignas();
+                attr.align = Some(attr.align.unwrap_or(0).max(align));
+                continue;
+            }
+            let is_tagged = ["struct", "union", "enum", "_Atomic"]
+                .iter()
+                .any(|kw| self.cur().matches(kw));
+            // an identifier after a type specifier is a declarator even if it names a type,
+            // as in `typedef int T; { long T; }`
+            let is_typedef_name =
+                named_ty.is_none() && specs.is_empty() && self.is_typedef_name();
+            if (is_tagged || self.is_type_keyword()) && named_ty.is_some()
+                || (is_tagged && !specs.is_empty())
+            {
+                panic!("Two or more data types in declaration specifiers");
+            }
+            if self.consume("struct") {
+                named_ty = Some(self.read_struct_decl(false));
+            } else if self.consume("union") {
+                named_ty = Some(self.read_struct_decl(true));
+            } else if self.consume("enum") {
+                named_ty = Some(self.read_enum_decl());
+            } else if self.consume("_Atomic") {
+                named_ty = Some(self.read_atomic_type());
+            } else if is_typedef_name {
+                let name = self.next().val;
+                named_ty = self.find_typedef(&name);
+            } else if self.is_type_keyword() {
+                specs.push(self.next().val);
+            } else {
+                break;
+            }
+        }
+        let ty = match named_ty {
+            Some(ty) => ty,
+            None => self.type_of_specs(specs),
+        };
+        (ty.qualified(quals), attr)
+    }
+
+    fn type_of_specs(&self, mut specs: Vec<String>) -> Type {
+        // type specifiers may appear in any order (e.g. "long unsigned int"),
+        // so sort them before looking up the combination
+        specs.sort();
+        match specs.join(" ").as_str() {
+            "void" => Type::Void,
+            "_Bool" => Type::Bool,
+            "char" | "char signed" => Type::Char,
+            "char unsigned" => Type::UChar,
+            "short" | "int short" | "short signed" | "int short signed" => Type::Short,
+            "short unsigned" | "int short unsigned" => Type::UShort,
+            "int" | "signed" | "int signed" => Type::Int,
+            "unsigned" | "int unsigned" => Type::UInt,
+            "long" | "int long" | "long signed" | "int long signed" => Type::Long,
+            "long unsigned" | "int long unsigned" => Type::ULong,
+            "long long" | "int long long" | "long long signed" | "int long long signed" => {
+                Type::LongLong
+            }
+            "long long unsigned" | "int long long unsigned" => Type::ULongLong,
+            "float" => Type::Float,
+            "double" => Type::Double,
+            "double long" => Type::LongDouble,
             _ => panic!("Unknown type"),
         }
     }
 
-    fn read_declarator(&mut self, mut ty: Type) -> (Type, String) {
+    fn find_tag(&self, tag: &String) -> Option<Type> {
+        for scope in self.tag_scopes.iter().rev() {
+            if let Some(ty) = scope.get(tag) {
+                return Some(ty.clone());
+            }
+        }
+        None
+    }
+
+    fn new_struct_type(&mut self, tag: Option<String>, is_union: bool) -> Type {
+        self.struct_cnt += 1;
+        let ty = Type::Struct(Rc::new(RefCell::new(StructDef::new(
+            self.struct_cnt,
+            tag.clone(),
+            is_union,
+        ))));
+        if let Some(tag) = tag {
+            self.tag_scopes
+                .last_mut()
+                .unwrap()
+                .insert(tag, ty.clone());
+        }
+        ty
+    }
+
+    fn read_struct_decl(&mut self, is_union: bool) -> Type {
+        let tag = if self.cur().is_ident() {
+            Some(self.read_ident())
+        } else {
+            None
+        };
+        let tag_name = tag.clone().unwrap_or_default();
+        let tag_in_cur_scope = match &tag {
+            Some(tag) => self.tag_scopes.last().unwrap().get(tag).cloned(),
+            None => None,
+        };
+
+        let ty = if !self.cur().matches("{") {
+            let tag = match tag {
+                Some(tag) => tag,
+                None => panic!("Expected {{, but found {}", self.cur().val),
+            };
+            if self.cur().matches(";") {
+                // `struct T;` declares a new incomplete type in the current scope
+                match tag_in_cur_scope {
+                    Some(ty) => ty,
+                    None => self.new_struct_type(Some(tag), is_union),
+                }
+            } else {
+                // reference to a declared type, or declaration of an incomplete type
+                match self.find_tag(&tag) {
+                    Some(ty) => ty,
+                    None => self.new_struct_type(Some(tag), is_union),
+                }
+            }
+        } else {
+            self.consume_expected("{");
+            // a definition completes the incomplete type declared in the same scope
+            let ty = match tag_in_cur_scope {
+                Some(Type::Struct(st)) if st.borrow().is_complete => {
+                    panic!("Redefinition of {}", Type::Struct(st.clone()))
+                }
+                Some(ty) => ty,
+                None => self.new_struct_type(tag, is_union),
+            };
+            let members = self.read_struct_members();
+            if let Type::Struct(st) = &ty {
+                st.borrow_mut().set_members(members);
+            }
+            ty
+        };
+
+        match &ty {
+            Type::Struct(st) if st.borrow().is_union == is_union => (),
+            _ => panic!(
+                "Use of {} with tag type that does not match previous declaration",
+                tag_name
+            ),
+        }
+        ty
+    }
+
+    // an enum type is represented by its compatible integer type
+    fn read_enum_decl(&mut self) -> Type {
+        let tag = if self.cur().is_ident() {
+            Some(self.read_ident())
+        } else {
+            None
+        };
+        if !self.consume("{") {
+            let tag = match tag {
+                Some(tag) => tag,
+                None => panic!("Expected {{, but found {}", self.cur().val),
+            };
+            return match self.find_tag(&tag) {
+                Some(Type::Struct(_)) => panic!(
+                    "Use of {} with tag type that does not match previous declaration",
+                    tag
+                ),
+                Some(ty) => ty,
+                None => panic!("Use of undeclared enum {}", tag),
+            };
+        }
+        if let Some(tag) = &tag {
+            if self.tag_scopes.last().unwrap().contains_key(tag) {
+                panic!("Redefinition of enum {}", tag);
+            }
+        }
+
+        // an enumerator can be used in the values of the following ones
+        let mut names = Vec::new();
+        let mut values = Vec::new();
+        let mut val = 0;
+        loop {
+            let name = self.read_ident();
+            if self.consume("=") {
+                val = self.read_const_int();
+            }
+            let ty = if i32::MIN as i64 <= val && val <= i32::MAX as i64 {
+                Type::Int
+            } else {
+                Type::Long
+            };
+            let enum_const = VarScope::EnumConst(val, ty);
+            self.check_redeclaration(&name, &enum_const);
+            self.var_scopes.last_mut().unwrap().insert(name.clone(), enum_const);
+            names.push(name);
+            values.push(val);
+            val += 1;
+            // a trailing comma is allowed
+            if self.consume("}") || (self.consume(",") && self.consume("}")) {
+                break;
+            }
+        }
+
+        // choose the compatible type as GCC does: unsigned int if there is no
+        // negative value, int otherwise, and long types for values out of their range
+        let min = *values.iter().min().unwrap();
+        let max = *values.iter().max().unwrap();
+        let ty = if min >= 0 && max <= u32::MAX as i64 {
+            Type::UInt
+        } else if min >= i32::MIN as i64 && max <= i32::MAX as i64 {
+            Type::Int
+        } else if min >= 0 {
+            Type::ULong
+        } else {
+            Type::Long
+        };
+        // enumerators have type int, or the enum type if some value does not fit in int
+        if min < i32::MIN as i64 || max > i32::MAX as i64 {
+            for (name, val) in names.into_iter().zip(values) {
+                self.var_scopes
+                    .last_mut()
+                    .unwrap()
+                    .insert(name, VarScope::EnumConst(val, ty.clone()));
+            }
+        }
+        if let Some(tag) = tag {
+            self.tag_scopes
+                .last_mut()
+                .unwrap()
+                .insert(tag, ty.clone());
+        }
+        ty
+    }
+
+    fn read_struct_members(&mut self) -> Vec<MemberDecl> {
+        let mut members = Vec::new();
+        while !self.consume("}") {
+            // members are laid out by the parser, so an assertion among them is checked here
+            if self.consume("_Static_assert") {
+                if let AST::StaticAssert(cond, msg) = self.read_static_assert() {
+                    let cond = const_eval::eval_int_const(&cond).unwrap_or_else(|err| panic!("{}", err));
+                    sema::check_static_assert(cond, &msg);
+                }
+                continue;
+            }
+            let (declspec, attr) = self.read_declspec_attr();
+            if attr.has_storage_class() || attr.is_thread_local {
+                panic!("Storage class specifier is not allowed in a struct or union");
+            }
+            // anonymous struct or union member
+            if self.consume(";") {
+                if declspec.is_struct() {
+                    members.push((None, declspec, None, attr.align));
+                }
+                continue;
+            }
+            members.push(self.read_struct_member(declspec.clone(), attr.align));
+            while self.consume(",") {
+                members.push(self.read_struct_member(declspec.clone(), attr.align));
+            }
+            self.consume_expected(";");
+        }
+        members
+    }
+
+    fn read_struct_member(&mut self, declspec: Type, align: Option<i32>) -> MemberDecl {
+        // unnamed bit-field
+        if self.consume(":") {
+            let width = self.read_const_int() as i32;
+            return (None, declspec, Some(width), align);
+        }
+        let (ty, name) = self.read_declarator(declspec);
+        if !self.vla_lens.is_empty() {
+            panic!("Member {} has variably modified type {}", name, ty);
+        }
+        let mut width = None;
+        if self.consume(":") {
+            width = Some(self.read_const_int() as i32);
+        }
+        (Some(name), ty, width, align)
+    }
+
+    fn read_declarator(&mut self, ty: Type) -> (Type, String) {
+        match self.read_declarator_opt(ty) {
+            (ty, Some(name)) => (ty, name),
+            (_, None) => panic!("Expected identifier, but found {}", self.cur().val),
+        }
+    }
+
+    // declarator without an identifier, used in type names (e.g. int (*)[4])
+    fn read_abstract_declarator(&mut self, ty: Type) -> Type {
+        match self.read_declarator_opt(ty) {
+            (ty, None) => ty,
+            (_, Some(name)) => panic!("Unexpected identifier {} in type name", name),
+        }
+    }
+
+    // declarator whose identifier may be omitted
+    fn read_declarator_opt(&mut self, mut ty: Type) -> (Type, Option<String>) {
+        // qualifiers after * apply to the pointer itself, e.g. int *const p
         while self.consume("*") {
-            ty = Type::Ptr(Box::new(ty));
+            let quals = self.read_qualifiers();
+            ty = Type::Ptr(Box::new(ty)).qualified(quals);
+        }
+        // a parenthesized declarator applies to the type made by the suffix after it,
+        // e.g. in int (*fp)(int), fp is a pointer to int (int).
+        // "(" followed by a type name or ")" starts the parameters of an abstract declarator.
+        if self.cur().matches("(") && !self.peek().matches(")") && !self.is_typename_at(self.pos + 1) {
+            self.next();
+            let start = self.pos;
+            self.skip_parens();
+            ty = self.read_type_suffix(ty);
+            let end = self.pos;
+            self.pos = start;
+            let (ty, name) = self.read_declarator_opt(ty);
+            self.consume_expected(")");
+            self.pos = end;
+            return (ty, name);
         }
-        let name = self.read_ident();
+        let name = if self.cur().is_ident() {
+            Some(self.read_ident())
+        } else {
+            None
+        };
         ty = self.read_type_suffix(ty);
         (ty, name)
     }
 
+    // skip tokens until the ")" which closes the current parenthesis
+    fn skip_parens(&mut self) {
+        let mut depth = 0;
+        loop {
+            if self.cur().is_eof() {
+                panic!("Expected ), but found end of file");
+            }
+            if self.cur().matches("(") {
+                depth += 1;
+            } else if self.cur().matches(")") {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            self.next();
+        }
+        self.next();
+    }
+
     fn read_type_suffix(&mut self, mut ty: Type) -> Type {
         if self.consume("[") {
-            let arr_sz = self.read_num();
-            self.consume_expected("]");
+            // the length of an array which could not be folded here
+            let mut vla_len = None;
+            // array of unknown size
+            let arr_sz = if self.consume("]") {
+                -1
+            } else if self.cur().matches("*") && self.peek().matches("]") {
+                // [*] is a variable length array of unspecified length, only in prototypes
+                self.next();
+                self.next();
+                vla_len = Some(AST::Nil);
+                -1
+            } else {
+                let len = self.read_equality();
+                self.consume_expected("]");
+                // sema decides whether a length which needs the types of variables (e.g.
+                // sizeof x) is constant
+                match const_eval::eval_int_const(&len) {
+                    Ok(arr_sz) if arr_sz < 0 => panic!("Size of array is negative"),
+                    Ok(arr_sz) => arr_sz as i32,
+                    Err(ConstError::NotConstant(_)) => {
+                        vla_len = Some(len);
+                        -1
+                    }
+                    Err(err) => panic!("Invalid size of array: {}", err),
+                }
+            };
             ty = self.read_type_suffix(ty);
-            ty = Type::Array(Box::new(ty), arr_sz as i32);
+            if matches!(ty, Type::Func(..)) {
+                panic!("Declared as array of functions");
+            }
+            if !ty.is_complete() {
+                panic!("Array has incomplete element type {}", ty);
+            }
+            // an array of variable length arrays has a variable size too
+            if ty.is_vla() && arr_sz >= 0 {
+                vla_len = Some(AST::Int(arr_sz as i64, Type::Int));
+            }
+            ty = match vla_len {
+                Some(len) => self.new_vla(ty, len),
+                None => Type::Array(Box::new(ty), arr_sz),
+            };
         } else if self.consume("(") {
-            let (types, names) = self.read_func_params();
-            // ret type, param types
-            return Type::Func(Box::new(ty), types, names);
+            if matches!(ty, Type::Func(..) | Type::Array(..) | Type::Vla(..)) {
+                panic!("Function cannot return {}", ty);
+            }
+            let mark = self.vla_lens.len();
+            let (types, names, is_variadic) = self.read_func_params();
+            self.param_vla_lens = self.vla_lens.split_off(mark);
+            // ret type, param types; qualifiers of the return type have no effect
+            return Type::Func(Box::new(ty.unqualified()), types, names, is_variadic);
+        }
+        ty
+    }
+
+    // a variable length array whose length is held by a new hidden variable; sema makes it
+    // an array if the length turns out to be constant
+    fn new_vla(&mut self, elem_ty: Type, len: AST) -> Type {
+        if let AST::Nil = len {
+            return Type::Vla(Box::new(elem_ty), String::new());
+        }
+        self.vla_cnt += 1;
+        let name = format!("vla.len.{}", self.vla_cnt);
+        self.vla_lens.push(AST::ArrayLen(name.clone(), Box::new(len)));
+        Type::Vla(Box::new(elem_ty), name)
+    }
+
+    // type name in casts and sizeof
+    fn read_typename(&mut self) -> Type {
+        let (ty, vla_lens) = self.read_typename_vla();
+        if !vla_lens.is_empty() {
+            panic!("Variably modified type {} is not supported here", ty);
         }
         ty
     }
 
-    fn read_func_params(&mut self) -> (Vec<Type>, Vec<String>) {
+    // type name which may have variable length arrays, and the declarations of their lengths
+    fn read_typename_vla(&mut self) -> (Type, Vec<AST>) {
+        let mark = self.vla_lens.len();
+        let ty = self.read_declspec();
+        let ty = self.read_abstract_declarator(ty);
+        (ty, self.vla_lens.split_off(mark))
+    }
+
+    // sizeof folded into a constant of type size_t, or computed at run time for a variable
+    // length array from its lengths, which are the hidden variables unless given in vla_lens
+    fn sizeof_expr(&self, ty: &Type, vla_lens: &[AST]) -> AST {
+        match ty {
+            Type::Vla(elem_ty, name) => {
+                let len = vla_lens
+                    .iter()
+                    .find_map(|decl| match decl {
+                        AST::ArrayLen(len_name, len) if len_name == name => Some(AST::Cast(len.clone(), Type::ULong)),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| AST::Load(Box::new(AST::Variable(name.clone()))));
+                let elem_size = self.sizeof_expr(elem_ty, vla_lens);
+                AST::BinaryOp(Box::new(len), Box::new(elem_size), BinaryOps::Mul)
+            }
+            _ => AST::Int(sizeof(ty) as i64, Type::ULong),
+        }
+    }
+
+    // param types, param names and whether the parameter list ends with ...
+    // an empty list declares a function without a prototype, and an identifier list
+    // (of an old-style definition) gives names without types
+    fn read_func_params(&mut self) -> (Vec<Type>, Vec<String>, bool) {
         let mut types = Vec::new();
         let mut names = Vec::new();
 
-        if !self.consume(")") {
-            let (ty, name) = self.read_param();
-            types.push(ty);
-            names.push(name);
+        // (void) means that the function takes no parameters
+        if self.cur().matches("void") && self.peek().matches(")") {
+            self.next();
+            self.next();
+            return (types, names, false);
+        }
+        if self.consume(")") {
+            return (types, names, true);
+        }
+        if self.cur().is_ident() && !self.is_typename() {
+            names.push(self.read_ident());
             while self.consume(",") {
-                let (ty, name) = self.read_param();
-                types.push(ty);
-                names.push(name);
+                names.push(self.read_ident());
             }
             self.consume_expected(")");
+            return (types, names, true);
+        }
+        if self.cur().matches("...") {
+            panic!("ISO C requires a named parameter before ...");
+        }
+        let mut is_variadic = false;
+        let (ty, name) = self.read_param();
+        types.push(ty);
+        names.push(name);
+        while self.consume(",") {
+            if self.consume("...") {
+                is_variadic = true;
+                break;
+            }
+            let (ty, name) = self.read_param();
+            types.push(ty);
+            names.push(name);
         }
-        (types, names)
+        self.consume_expected(")");
+        (types, names, is_variadic)
     }
 
+    // an unnamed parameter has an empty name
     fn read_param(&mut self) -> (Type, String) {
-        let ty = self.read_declspec();
-        let (ty, name) = self.read_declarator(ty);
-        (ty, name)
+        // register is the only storage class allowed, and is ignored
+        let (ty, attr) = self.read_declspec_attr();
+        if attr.has_storage_class() && !attr.is_register || attr.is_thread_local {
+            panic!("Invalid storage class for a parameter");
+        }
+        if attr.align.is_some() {
+            panic!("_Alignas is not allowed here");
+        }
+        let (ty, name) = self.read_declarator_opt(ty);
+        (adjust_param_type(ty), name.unwrap_or_default())
     }
 
     fn read_expr_stmt(&mut self) -> AST {
@@ -320,12 +1323,12 @@ impl Parser {
     }
 
     fn read_mul(&mut self) -> AST {
-        let mut ast = self.read_unary();
+        let mut ast = self.read_cast();
         loop {
             if self.consume("*") {
-                ast = AST::BinaryOp(Box::new(ast), Box::new(self.read_unary()), BinaryOps::Mul);
+                ast = AST::BinaryOp(Box::new(ast), Box::new(self.read_cast()), BinaryOps::Mul);
             } else if self.consume("/") {
-                ast = AST::BinaryOp(Box::new(ast), Box::new(self.read_unary()), BinaryOps::Div);
+                ast = AST::BinaryOp(Box::new(ast), Box::new(self.read_cast()), BinaryOps::Div);
             } else {
                 break;
             }
@@ -333,29 +1336,94 @@ impl Parser {
         ast
     }
 
+    fn read_cast(&mut self) -> AST {
+        if self.cur().matches("(") && self.is_typename_at(self.pos + 1) {
+            self.next();
+            let ty = self.read_typename();
+            self.consume_expected(")");
+            if self.cur().matches("{") {
+                let literal = self.read_compound_literal(ty);
+                return self.read_postfix_ops(literal);
+            }
+            return AST::Cast(Box::new(self.read_cast()), ty);
+        }
+        self.read_unary()
+    }
+
+    // (type-name){initializer-list}, whose type may be an array of unknown size
+    fn read_compound_literal(&mut self, ty: Type) -> AST {
+        if matches!(ty, Type::Func(..)) || !ty.is_complete() && !matches!(ty, Type::Array(_, -1)) {
+            panic!("Compound literal has incomplete type {}", ty);
+        }
+        let init = self.read_initializer();
+        AST::CompoundLiteral(ty, Box::new(init))
+    }
+
     fn read_unary(&mut self) -> AST {
         if self.consume("+") {
-            return AST::UnaryOp(Box::new(self.read_unary()), UnaryOps::Plus);
+            return AST::UnaryOp(Box::new(self.read_cast()), UnaryOps::Plus);
         } else if self.consume("-") {
-            return AST::UnaryOp(Box::new(self.read_unary()), UnaryOps::Minus);
+            return AST::UnaryOp(Box::new(self.read_cast()), UnaryOps::Minus);
         } else if self.consume("&") {
-            return AST::UnaryOp(Box::new(self.read_unary()), UnaryOps::Addr);
+            return AST::UnaryOp(Box::new(self.read_cast()), UnaryOps::Addr);
         } else if self.consume("*") {
-            return AST::UnaryOp(Box::new(self.read_unary()), UnaryOps::Deref);
+            return AST::UnaryOp(Box::new(self.read_cast()), UnaryOps::Deref);
+        } else if self.consume("sizeof") {
+            if self.cur().matches("(") && self.is_typename_at(self.pos + 1) {
+                self.next();
+                let (ty, vla_lens) = self.read_typename_vla();
+                self.consume_expected(")");
+                if self.cur().matches("{") {
+                    if !vla_lens.is_empty() {
+                        panic!("Compound literal has variable length array type {}", ty);
+                    }
+                    let literal = self.read_compound_literal(ty);
+                    let operand = self.read_postfix_ops(literal);
+                    return AST::Unevaluated(TypeQuery::SizeOf, Box::new(operand));
+                }
+                return self.sizeof_expr(&ty, &vla_lens);
+            }
+            return AST::Unevaluated(TypeQuery::SizeOf, Box::new(self.read_unary()));
+        } else if self.consume("_Alignof") {
+            if self.cur().matches("(") && self.is_typename_at(self.pos + 1) {
+                self.next();
+                let ty = self.read_typename();
+                self.consume_expected(")");
+                if !ty.is_complete() || matches!(ty, Type::Func(..)) {
+                    panic!("Invalid application of _Alignof to type {}", ty);
+                }
+                return AST::Int(ty.align() as i64, Type::ULong);
+            }
+            // _Alignof of an expression is a GNU extension
+            return AST::Unevaluated(TypeQuery::AlignOf, Box::new(self.read_unary()));
         }
         self.read_postfix()
     }
 
     fn read_postfix(&mut self) -> AST {
-        let mut ret = self.read_primary();
-        // x[y] is short for *(x+y)
-        if self.consume("[") {
-            let rhs = self.read_expr();
-            self.consume_expected("]");
-            ret = AST::UnaryOp(
-                Box::new(AST::BinaryOp(Box::new(ret), Box::new(rhs), BinaryOps::Add)),
-                UnaryOps::Deref,
-            );
+        let primary = self.read_primary();
+        self.read_postfix_ops(primary)
+    }
+
+    fn read_postfix_ops(&mut self, mut ret: AST) -> AST {
+        loop {
+            if self.consume("[") {
+                let rhs = self.read_expr();
+                self.consume_expected("]");
+                ret = AST::Index(Box::new(ret), Box::new(rhs));
+            } else if self.consume(".") {
+                ret = AST::Member(Box::new(ret), self.read_ident());
+            } else if self.consume("(") {
+                ret = AST::FuncCall(Box::new(ret), self.read_func_args());
+            } else if self.consume("->") {
+                // x->y is short for (*x).y
+                ret = AST::Member(
+                    Box::new(AST::UnaryOp(Box::new(ret), UnaryOps::Deref)),
+                    self.read_ident(),
+                );
+            } else {
+                break;
+            }
         }
         ret
     }
@@ -365,23 +1433,94 @@ impl Parser {
             let ast = self.read_expr();
             self.consume_expected(")");
             return ast;
-        } else if self.consume("sizeof") {
-            let ast = self.read_unary();
-            return AST::UnaryOp(Box::new(ast), UnaryOps::Sizeof);
         } else if self.cur().is_ident() {
-            if self.peek().matches("(") {
-                return self.read_func_call();
+            let name = self.read_ident();
+            if name.starts_with("__builtin_va_") && self.cur().matches("(") {
+                return self.read_va_builtin(&name);
             }
-            return AST::Load(Box::new(AST::Variable(self.read_ident())));
+            if let Some(VarScope::EnumConst(val, ty)) = self.find_var(&name) {
+                return AST::Int(val, ty);
+            }
+            return AST::Load(Box::new(AST::Variable(name)));
+        } else if self.cur().is_str() {
+            return AST::Load(Box::new(AST::Str(self.read_str_bytes())));
+        } else if self.consume("_Generic") {
+            return self.read_generic();
         } else {
             return self.read_ast_num();
         }
     }
 
-    fn read_func_call(&mut self) -> AST {
-        let name = self.read_ident();
-        let mut args = Vec::new();
+    // adjacent string literals are concatenated
+    fn read_str_bytes(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        while self.cur().is_str() {
+            bytes.extend(self.next().val.chars().map(|c| c as u8));
+        }
+        bytes
+    }
+
+    // _Generic(expr, type: expr, ..., default: expr), which sema replaces by the expression
+    // associated with the type of the controlling expression after lvalue conversion; the
+    // controlling expression and the other associations are not evaluated
+    fn read_generic(&mut self) -> AST {
         self.consume_expected("(");
+        let ctrl = self.read_assign();
+        let mut assocs: Vec<(Type, AST)> = Vec::new();
+        let mut default = None;
+        while self.consume(",") {
+            if self.consume("default") {
+                self.consume_expected(":");
+                if default.is_some() {
+                    panic!("Duplicate default association in _Generic");
+                }
+                default = Some(Box::new(self.read_assign()));
+                continue;
+            }
+            let ty = self.read_typename();
+            if !ty.is_complete() || ty.is_func() {
+                panic!("Invalid type {} in _Generic association", ty);
+            }
+            if assocs.iter().any(|(assoc_ty, _)| sema::is_compatible(assoc_ty, &ty)) {
+                panic!("Type {} is compatible with a previous association in _Generic", ty);
+            }
+            self.consume_expected(":");
+            let expr = self.read_assign();
+            assocs.push((ty, expr));
+        }
+        self.consume_expected(")");
+        AST::Unevaluated(TypeQuery::Generic(assocs, default), Box::new(ctrl))
+    }
+
+    // __builtin_va_start(ap, last), __builtin_va_arg(ap, type), __builtin_va_end(ap)
+    // and __builtin_va_copy(dest, src)
+    fn read_va_builtin(&mut self, name: &str) -> AST {
+        self.consume_expected("(");
+        let ap = Box::new(self.read_assign());
+        let ast = match name {
+            "__builtin_va_start" => {
+                // the last named parameter is not needed to find the variable arguments
+                self.consume_expected(",");
+                self.read_assign();
+                AST::VaStart(ap)
+            }
+            "__builtin_va_arg" => {
+                self.consume_expected(",");
+                AST::VaArg(ap, self.read_typename())
+            }
+            "__builtin_va_end" => AST::VaEnd(ap),
+            "__builtin_va_copy" => {
+                self.consume_expected(",");
+                AST::VaCopy(ap, Box::new(self.read_assign()))
+            }
+            _ => panic!("Unknown builtin {}", name),
+        };
+        self.consume_expected(")");
+        ast
+    }
+
+    fn read_func_args(&mut self) -> Vec<AST> {
+        let mut args = Vec::new();
         if !self.consume(")") {
             args.push(self.read_assign());
             while self.consume(",") {
@@ -389,7 +1528,7 @@ impl Parser {
             }
             self.consume_expected(")");
         }
-        AST::FuncCall(name, args)
+        args
     }
 
     fn read_ast_num(&mut self) -> AST {
@@ -398,28 +1537,18 @@ impl Parser {
                 kind: TokenKind::IntNum,
                 val: n,
                 ..
-            } => AST::Int(n.parse::<i32>().unwrap()),
-            Token {
-                kind: TokenKind::FloatNum,
-                val: n,
-                ..
-            } => AST::Float(n.parse::<f64>().unwrap()),
-            _ => panic!("Numerical literal is expected"),
-        }
-    }
-
-    fn read_num(&mut self) -> f64 {
-        match self.next() {
-            Token {
-                kind: TokenKind::IntNum,
-                val: n,
-                ..
-            } => n.parse::<f64>().unwrap(),
+            } => {
+                let (n, ty) = parse_int_literal(&n);
+                AST::Int(n, ty)
+            }
             Token {
                 kind: TokenKind::FloatNum,
                 val: n,
                 ..
-            } => n.parse::<f64>().unwrap(),
+            } => {
+                let (n, ty) = split_float_suffix(&n);
+                AST::Float(n.parse::<f64>().unwrap(), ty)
+            }
             _ => panic!("Numerical literal is expected"),
         }
     }
@@ -0,0 +1,1780 @@
+use crate::const_eval;
+use crate::const_eval::{ConstError, ConstResult, ConstValue};
+use crate::node;
+use crate::types;
+use crate::types::{BitField, Type};
+use crate::warning::{Warning, WarningOptions};
+
+use node::{BinaryOps, Designator, InitElem, InitLeaf, TypeQuery, UnaryOps, VarAttr, AST};
+use std::collections::HashMap;
+
+// Semantic analysis: give every expression its C type and make all implicit
+// conversions explicit as AST::Cast, so that both operands of an arithmetic
+// operator, the rhs of an assignment, arguments and return values always have
+// the type codegen expects.
+//
+// Expressions in the returned AST are one of
+// - lvalues: AST::Variable, AST::UnaryOp(ptr, Deref), AST::Member and AST::Index,
+//   which codegen lowers to addresses
+// - rvalues: everything else, where AST::Load(lvalue) reads the value of an lvalue
+pub fn run(program: Vec<AST>, options: Options) -> Vec<AST> {
+    let mut sema = Sema::new();
+    sema.options = options;
+    sema.check_program(program)
+}
+
+// command-line options that affect semantic analysis
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub warnings: WarningOptions, // -W options
+}
+
+pub fn is_compatible(ty1: &Type, ty2: &Type) -> bool {
+    match (ty1, ty2) {
+        // e.g. int (*)(void) and int (*)()
+        (Type::Ptr(base1), Type::Ptr(base2)) => base1.qualifiers() == base2.qualifiers() && is_same_pointee(ty1, ty2),
+        _ => composite_type(ty1, ty2).is_some(),
+    }
+}
+
+// _Static_assert, whose condition has been folded
+pub fn check_static_assert(cond: i64, msg: &Option<Vec<u8>>) {
+    if cond == 0 {
+        match msg {
+            Some(msg) => panic!("Static assertion failed: \"{}\"", String::from_utf8_lossy(msg)),
+            None => panic!("Static assertion failed"),
+        }
+    }
+}
+
+// the statement does nothing when it is executed
+fn is_declaration_only(ast: &AST) -> bool {
+    match ast {
+        AST::VariableDecl(_, _, None, _) | AST::FuncDecl(..) | AST::Nil => true,
+        AST::VariableDecl(_, _, Some(_), attr) => attr.is_static || attr.is_extern,
+        _ => false,
+    }
+}
+
+// the size of an object of type ty, computed at run time for a variable length array
+fn sizeof_expr(ty: &Type) -> AST {
+    match ty.strip_qual() {
+        Type::Vla(elem_ty, name) => AST::BinaryOp(
+            Box::new(AST::Load(Box::new(AST::Variable(name.clone())))),
+            Box::new(sizeof_expr(elem_ty)),
+            BinaryOps::Mul,
+        ),
+        ty => AST::Int(ty.size() as i64, Type::ULong),
+    }
+}
+
+pub struct Sema {
+    global_varmap: HashMap<String, Type>,
+    global_decls: HashMap<String, usize>, // index of the declaration of each global variable
+    linkages: HashMap<String, Linkage>,   // linkage of each identifier declared at file scope
+    thread_locals: HashMap<String, bool>, // whether each global variable is _Thread_local
+    local_varmap: Vec<HashMap<String, (Type, VarAttr)>>,
+    // the variables in each scope of local_varmap which are not used yet, and whether each
+    // is a parameter
+    unused_locals: Vec<Vec<(String, bool)>>,
+    cur_ret_ty: Option<Type>,
+    cur_is_variadic: bool, // whether the current function takes variable arguments
+    options: Options,
+    // lengths of arrays which the parser could not fold, but which are integer constant
+    // expressions (e.g. int a[sizeof x]), by their hidden variables
+    array_lens: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Linkage {
+    External,
+    Internal,
+}
+
+fn is_null_pointer_constant(ast: &AST) -> bool {
+    matches!(ast, AST::Int(0, _))
+}
+
+// void * converts implicitly to and from any object pointer
+fn is_void_ptr_conversion(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (Type::Ptr(from_base), Type::Ptr(to_base)) => from_base.is_void() || to_base.is_void(),
+        _ => false,
+    }
+}
+
+// pointers to the same type, ignoring the qualifiers of the pointed-to type
+fn is_same_pointee(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        // e.g. int (*)() and int (*)(int)
+        (Type::Ptr(from_base), Type::Ptr(to_base)) if from_base.is_func() => {
+            composite_type(from_base, to_base).is_some()
+        }
+        // e.g. int (*)[n] and int (*)[3], whose lengths are checked only at run time
+        (Type::Ptr(from_base), Type::Ptr(to_base)) if from_base.is_vla() || to_base.is_vla() => {
+            match (from_base.strip_qual(), to_base.strip_qual()) {
+                (Type::Array(from_elem, _) | Type::Vla(from_elem, _), Type::Array(to_elem, _) | Type::Vla(to_elem, _)) => {
+                    is_same_pointee(&Type::Ptr(from_elem.clone()), &Type::Ptr(to_elem.clone()))
+                }
+                _ => false,
+            }
+        }
+        (Type::Ptr(from_base), Type::Ptr(to_base)) => from_base.unqualified() == to_base.unqualified(),
+        _ => false,
+    }
+}
+
+// an object of struct type cannot be assigned if any of its members is const
+fn has_const_member(ty: &Type) -> bool {
+    match ty.strip_qual() {
+        Type::Struct(st) => st
+            .borrow()
+            .members
+            .iter()
+            .any(|member| member.ty.is_const() || has_const_member(&member.ty)),
+        Type::Array(elem_ty, _) => has_const_member(elem_ty),
+        _ => false,
+    }
+}
+
+fn check_object_type(ty: &Type, name: &String) {
+    if !ty.is_complete() {
+        panic!("Variable {} has incomplete type {}", name, ty);
+    }
+}
+
+fn check_alignas(ty: &Type, name: &String, attr: &VarAttr) {
+    if let Some(align) = attr.align {
+        if align != 0 && align < ty.align() {
+            panic!("_Alignas cannot reduce the alignment of {} below {}", name, ty.align());
+        }
+    }
+}
+
+// the type of an identifier declared twice, if the two types are compatible;
+// an array of unknown size is completed by the other declaration
+fn composite_type(prev: &Type, ty: &Type) -> Option<Type> {
+    match (prev, ty) {
+        // a declaration without a prototype is compatible with a prototype whose parameters
+        // are not changed by the default argument promotions
+        (Type::Func(prev_ret, ..), Type::Func(ret, param_types, _, is_variadic))
+            if prev.is_unprototyped() && prev_ret == ret =>
+        {
+            let is_compatible = ty.is_unprototyped()
+                || !is_variadic && param_types.iter().all(|param_ty| param_ty.default_promoted() == param_ty.unqualified());
+            if is_compatible {
+                Some(ty.clone())
+            } else {
+                None
+            }
+        }
+        (Type::Func(..), Type::Func(..)) if ty.is_unprototyped() => composite_type(ty, prev),
+        (Type::Array(prev_elem, prev_len), Type::Array(elem, len)) if prev_elem == elem => {
+            if *prev_len == -1 {
+                Some(ty.clone())
+            } else if *len == -1 || len == prev_len {
+                Some(prev.clone())
+            } else {
+                None
+            }
+        }
+        _ if prev == ty => Some(prev.clone()),
+        _ => None,
+    }
+}
+
+fn is_va_list_tag(ty: &Type) -> bool {
+    match ty.strip_qual() {
+        Type::Struct(st) => st.borrow().tag.as_deref() == Some("__va_list_tag"),
+        _ => false,
+    }
+}
+
+fn check_func_storage_class(name: &str, attr: &VarAttr) {
+    if attr.is_auto || attr.is_register || attr.is_thread_local {
+        panic!("Invalid storage class for function {}", name);
+    }
+}
+
+// whether the expression designates an object, before it is checked
+fn is_lvalue(ast: &AST) -> bool {
+    match ast {
+        AST::Load(_)
+        | AST::Variable(_)
+        | AST::Str(_)
+        | AST::CompoundLiteral(..)
+        | AST::UnaryOp(_, UnaryOps::Deref)
+        | AST::Index(..) => true,
+        AST::Member(expr, _) => is_lvalue(expr),
+        _ => false,
+    }
+}
+
+// the type of the member, and its layout if it is a bit-field;
+// a member of a const or volatile struct is also const or volatile
+fn member_type(ty: &Type, name: &String) -> (Type, Option<BitField>) {
+    let st = match ty.strip_qual() {
+        Type::Struct(st) => st.clone(),
+        _ => panic!("Member reference base type {} is not a structure or union", ty),
+    };
+    if !st.borrow().is_complete {
+        panic!("Member access into incomplete type {}", ty);
+    }
+    let path = match st.borrow().find_member(name) {
+        Some(path) => path,
+        None => panic!("No member named {} in {}", name, ty),
+    };
+    let quals = types::Qualifiers {
+        is_const: ty.is_const(),
+        is_volatile: ty.qualifiers().is_volatile,
+        ..Default::default()
+    };
+    let mut ty = ty.clone();
+    let mut bit_field = None;
+    for i in path {
+        (ty, bit_field) = match ty.strip_qual() {
+            Type::Struct(st) => {
+                let member = &st.borrow().members[i];
+                (member.ty.clone().qualified(quals), member.bit_field)
+            }
+            _ => unreachable!(),
+        };
+    }
+    (ty, bit_field)
+}
+
+// an initializer resolved against the type of the object, with a node for each subobject
+enum InitTree {
+    Zero,
+    Expr(AST),    // converted to the type of the subobject
+    Str(Vec<u8>), // char array initialized by a string literal, truncated to the array
+    Array(Vec<InitTree>),
+    Struct(Vec<InitTree>),
+    Union(usize, Box<InitTree>), // the initialized member
+}
+
+impl InitTree {
+    // an initializer of an aggregate whose subobjects are all zero
+    fn new(ty: &Type) -> InitTree {
+        match ty.strip_qual() {
+            Type::Array(_, len) => InitTree::Array((0..(*len).max(0)).map(|_| InitTree::Zero).collect()),
+            Type::Struct(st) if !st.borrow().is_union => {
+                InitTree::Struct(st.borrow().members.iter().map(|_| InitTree::Zero).collect())
+            }
+            _ => InitTree::Zero,
+        }
+    }
+
+    fn is_aggregate(&self) -> bool {
+        matches!(self, InitTree::Array(_) | InitTree::Struct(_) | InitTree::Union(..))
+    }
+
+    // the node of the i-th subobject; an array of unknown size grows as needed, and
+    // initializing a member of a union discards the initializer of the other member
+    fn child(&mut self, ty: &Type, i: usize) -> &mut InitTree {
+        if let Type::Struct(st) = ty.strip_qual() {
+            if st.borrow().is_union && !matches!(self, InitTree::Union(member, _) if *member == i) {
+                let member_ty = &st.borrow().members[i].ty;
+                *self = InitTree::Union(i, Box::new(InitTree::new(member_ty)));
+            }
+        }
+        match self {
+            InitTree::Array(elems) => {
+                if elems.len() <= i {
+                    elems.resize_with(i + 1, || InitTree::Zero);
+                }
+                &mut elems[i]
+            }
+            InitTree::Struct(members) => &mut members[i],
+            InitTree::Union(_, member) => member,
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn is_aggregate(ty: &Type) -> bool {
+    matches!(ty.strip_qual(), Type::Array(..) | Type::Struct(_))
+}
+
+fn is_char_array(ty: &Type) -> bool {
+    match ty {
+        Type::Array(elem_ty, _) => matches!(elem_ty.strip_qual(), Type::Char | Type::UChar),
+        _ => false,
+    }
+}
+
+fn is_str_literal(ast: &AST) -> bool {
+    matches!(ast, AST::Load(lvalue) if matches!(**lvalue, AST::Str(_)))
+}
+
+// the type of the i-th subobject of an aggregate
+fn subobject_type(ty: &Type, i: usize) -> Type {
+    match ty.strip_qual() {
+        Type::Array(elem_ty, _) => (**elem_ty).clone(),
+        Type::Struct(st) => st.borrow().members[i].ty.clone(),
+        _ => unreachable!(),
+    }
+}
+
+// the subobject initialized by the next initializer without a designator, from the i-th one;
+// unnamed bit-fields are skipped, and only the first member of a union is initialized
+fn next_subobject(ty: &Type, i: usize) -> Option<usize> {
+    match ty.strip_qual() {
+        Type::Array(_, len) if *len < 0 || (i as i32) < *len => Some(i),
+        Type::Struct(st) => {
+            let st = st.borrow();
+            if st.is_union && i > 0 {
+                return None;
+            }
+            (i..st.members.len()).find(|&i| {
+                let member = &st.members[i];
+                member.name.is_some() || member.bit_field.is_none()
+            })
+        }
+        _ => None,
+    }
+}
+
+// the leaves of an initializer in the order of the tree
+fn flatten_init(ty: &Type, tree: InitTree, offset: i32, leaves: &mut Vec<InitLeaf>) {
+    match tree {
+        InitTree::Zero => (),
+        InitTree::Expr(expr) => leaves.push(InitLeaf {
+            offset,
+            ty: ty.clone(),
+            bit_field: None,
+            expr,
+        }),
+        InitTree::Str(s) => leaves.push(InitLeaf {
+            offset,
+            ty: ty.clone(),
+            bit_field: None,
+            expr: AST::Str(s),
+        }),
+        InitTree::Array(elems) => {
+            let elem_ty = subobject_type(ty, 0);
+            for (i, elem) in elems.into_iter().enumerate() {
+                flatten_init(&elem_ty, elem, offset + i as i32 * elem_ty.size(), leaves);
+            }
+        }
+        InitTree::Struct(members) => {
+            for (i, member) in members.into_iter().enumerate() {
+                flatten_member(ty, i, member, offset, leaves);
+            }
+        }
+        InitTree::Union(i, member) => flatten_member(ty, i, *member, offset, leaves),
+    }
+}
+
+fn flatten_member(ty: &Type, i: usize, tree: InitTree, offset: i32, leaves: &mut Vec<InitLeaf>) {
+    let (member_ty, member_offset, bit_field) = match ty.strip_qual() {
+        Type::Struct(st) => {
+            let member = &st.borrow().members[i];
+            (member.ty.clone(), member.offset, member.bit_field)
+        }
+        _ => unreachable!(),
+    };
+    match (tree, bit_field) {
+        (InitTree::Expr(expr), Some(bit_field)) => leaves.push(InitLeaf {
+            offset: offset + member_offset,
+            ty: member_ty,
+            bit_field: Some(bit_field),
+            expr,
+        }),
+        (tree, _) => flatten_init(&member_ty, tree, offset + member_offset, leaves),
+    }
+}
+
+// a bit-field narrower than int is promoted to int like a narrow integer type
+fn load_bit_field(lvalue: AST, ty: Type, bit_field: Option<BitField>) -> (AST, Type) {
+    let load = AST::Load(Box::new(lvalue));
+    match bit_field {
+        Some(bit_field) if bit_field.width < Type::Int.size() * 8 && ty != Type::Int => {
+            (AST::Cast(Box::new(load), Type::Int), Type::Int)
+        }
+        _ => (load, ty),
+    }
+}
+
+impl Sema {
+    fn new() -> Sema {
+        Sema {
+            global_varmap: HashMap::new(),
+            global_decls: HashMap::new(),
+            linkages: HashMap::new(),
+            thread_locals: HashMap::new(),
+            local_varmap: Vec::new(),
+            unused_locals: Vec::new(),
+            cur_ret_ty: None,
+            cur_is_variadic: false,
+            options: Options::default(),
+            array_lens: HashMap::new(),
+        }
+    }
+
+    fn check_program(&mut self, program: Vec<AST>) -> Vec<AST> {
+        // declarations are visible from where they appear, so a function called before its
+        // definition must be declared earlier (or is declared implicitly)
+        let mut defined = Vec::new();
+        let mut ret = Vec::new();
+        for top_level in program {
+            match top_level {
+                AST::FuncDef(func_ty, func_name, body, attr) => {
+                    if defined.contains(&func_name) {
+                        panic!("Redefinition of {}", func_name);
+                    }
+                    defined.push(func_name.clone());
+                    self.declare_func(&func_name, &func_ty);
+                    ret.push(self.check_func_def(func_ty, func_name, *body, attr));
+                }
+                AST::FuncDecl(func_ty, func_name, mut attr) => {
+                    check_func_storage_class(&func_name, &attr);
+                    attr.is_static = self.declare_linkage(&func_name, &attr, true) == Linkage::Internal;
+                    self.declare_func(&func_name, &func_ty);
+                    ret.push(AST::FuncDecl(func_ty, func_name, attr));
+                }
+                AST::VariableDecl(ty, name, init_opt, attr) => {
+                    self.check_global_var_decl(&mut ret, ty, name, init_opt, attr);
+                }
+                // objects at file scope have static storage, whose size must be constant
+                AST::ArrayLen(name, len) => {
+                    self.check_array_len(name.clone(), *len);
+                    if !self.array_lens.contains_key(&name) {
+                        panic!("Variably modified type at file scope");
+                    }
+                }
+                AST::StaticAssert(cond, msg) => self.fold_static_assert(*cond, &msg),
+                _ => panic!("Unsupported node type"),
+            }
+        }
+        // all declarations of a function are declared with the composite type of them, and
+        // functions declared only in blocks (including implicitly) are declared at file scope
+        for decl in ret.iter_mut() {
+            if let AST::FuncDecl(func_ty, func_name, _) = decl {
+                **func_ty = self.global_varmap[func_name].clone();
+            }
+        }
+        let mut names: Vec<&String> = self.global_varmap.keys().collect();
+        names.sort();
+        for name in names {
+            let ty = &self.global_varmap[name];
+            let is_declared = ret.iter().any(|decl| match decl {
+                AST::FuncDef(_, func_name, ..) | AST::FuncDecl(_, func_name, _) => func_name == name,
+                _ => false,
+            });
+            if ty.is_func() && !is_declared {
+                let attr = VarAttr {
+                    is_static: self.linkages.get(name) == Some(&Linkage::Internal),
+                    ..VarAttr::default()
+                };
+                ret.push(AST::FuncDecl(Box::new(ty.clone()), name.clone(), attr));
+            }
+        }
+        // a tentative definition (one without an initializer) of an array of unknown size
+        // defines an array of one element
+        for decl in ret.iter_mut() {
+            if let AST::VariableDecl(ty, name, init_opt, attr) = decl {
+                // an extern declaration refers to an object defined in another translation unit
+                if attr.is_extern && init_opt.is_none() {
+                    continue;
+                }
+                if let Type::Array(_, len) = ty {
+                    if *len == -1 {
+                        *len = 1;
+                    }
+                }
+                check_object_type(ty, name);
+            }
+        }
+        ret
+    }
+
+    // all declarations of a global variable refer to the same object, so they are merged
+    // into the first one; at most one of them can have an initializer
+    fn check_global_var_decl(
+        &mut self,
+        decls: &mut Vec<AST>,
+        ty: Type,
+        name: String,
+        init_opt: Option<Box<AST>>,
+        mut attr: VarAttr,
+    ) {
+        let ty = self.resolve_type(ty);
+        check_alignas(&ty, &name, &attr);
+        if attr.is_auto || attr.is_register {
+            panic!("File-scope declaration of {} specifies auto or register", name);
+        }
+        attr.is_static = self.declare_linkage(&name, &attr, false) == Linkage::Internal;
+        self.declare_thread_local(&name, &attr);
+        let ty = match self.global_varmap.get(&name) {
+            Some(prev_ty) => match composite_type(prev_ty, &ty) {
+                Some(ty) => ty,
+                None => panic!("Conflicting types for {}", name),
+            },
+            None => ty,
+        };
+        self.global_varmap.insert(name.clone(), ty.clone());
+        let (init_opt, ty) = match init_opt {
+            Some(init) => {
+                let (init, ty) = self.check_const_init(*init, &ty, &name);
+                self.global_varmap.insert(name.clone(), ty.clone());
+                (Some(Box::new(init)), ty)
+            }
+            None => (None, ty),
+        };
+        match self.global_decls.get(&name) {
+            Some(&i) => {
+                if let AST::VariableDecl(prev_ty, _, prev_init, prev_attr) = &mut decls[i] {
+                    if init_opt.is_some() {
+                        if prev_init.is_some() {
+                            panic!("Redefinition of {}", name);
+                        }
+                        *prev_init = init_opt;
+                    }
+                    *prev_ty = ty;
+                    prev_attr.is_extern &= attr.is_extern;
+                    prev_attr.is_static = attr.is_static;
+                    prev_attr.align = prev_attr.align.max(attr.align);
+                }
+            }
+            None => {
+                self.global_decls.insert(name.clone(), decls.len());
+                decls.push(AST::VariableDecl(ty, name, init_opt, attr));
+            }
+        }
+    }
+
+    // the linkage of an identifier declared at file scope, or by extern in a block;
+    // a declaration with extern (which functions have implicitly) inherits the linkage
+    // of the previous declaration
+    fn declare_linkage(&mut self, name: &String, attr: &VarAttr, is_func: bool) -> Linkage {
+        let prev = self.linkages.get(name).copied();
+        let linkage = if attr.is_static {
+            Linkage::Internal
+        } else if attr.is_extern || is_func {
+            prev.unwrap_or(Linkage::External)
+        } else {
+            Linkage::External
+        };
+        match (prev, linkage) {
+            (Some(Linkage::External), Linkage::Internal) => {
+                panic!("Static declaration of {} follows non-static declaration", name)
+            }
+            (Some(Linkage::Internal), Linkage::External) => {
+                panic!("Non-static declaration of {} follows static declaration", name)
+            }
+            _ => (),
+        }
+        self.linkages.insert(name.clone(), linkage);
+        linkage
+    }
+
+    // all declarations of a function in the translation unit (at file scope or in blocks)
+    // refer to the same function, so they must have compatible types; the composite type
+    // of them is the type of the function from then on
+    fn declare_func(&mut self, name: &String, ty: &Type) -> Type {
+        let ty = match self.global_varmap.get(name) {
+            Some(prev_ty) => match composite_type(prev_ty, ty) {
+                Some(ty) => ty,
+                None => panic!("Conflicting types for {}: {} and {}", name, prev_ty, ty),
+            },
+            None => ty.clone(),
+        };
+        self.global_varmap.insert(name.clone(), ty.clone());
+        ty
+    }
+
+    // all declarations of a global variable must agree on _Thread_local
+    fn declare_thread_local(&mut self, name: &String, attr: &VarAttr) {
+        let prev = self.thread_locals.insert(name.clone(), attr.is_thread_local);
+        if matches!(prev, Some(prev) if prev != attr.is_thread_local) {
+            panic!("Thread-local and non-thread-local declarations of {}", name);
+        }
+    }
+
+    // a name can be declared only once in a block, except for declarations with linkage
+    // (extern variables and functions), which all refer to the same entity
+    fn declare_local(&mut self, name: &str, ty: &Type, attr: &VarAttr) {
+        let has_linkage = attr.is_extern || ty.is_func();
+        if let Some((prev_ty, prev_attr)) = self.local_varmap.last().unwrap().get(name) {
+            if !(has_linkage && (prev_attr.is_extern || prev_ty.is_func())) {
+                panic!("Redefinition of {}", name);
+            }
+        } else if !has_linkage {
+            if self.lookup_local(&name.to_string()).is_some() {
+                self.warn(Warning::Shadow, &format!("Declaration of {} shadows a previous local declaration", name));
+            } else if matches!(self.global_varmap.get(name), Some(ty) if !ty.is_func()) {
+                self.warn(Warning::Shadow, &format!("Declaration of {} shadows a global declaration", name));
+            }
+        }
+        self.local_varmap
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), (ty.clone(), attr.clone()));
+        // variables made by the compiler (e.g. vla.len.1) have a dot in their names
+        if !has_linkage && !name.is_empty() && !name.contains('.') {
+            let unused = self.unused_locals.last_mut().unwrap();
+            unused.retain(|(unused_name, _)| unused_name != name);
+            unused.push((name.to_string(), false));
+        }
+    }
+
+    // an array of unknown size declared in a block is completed by its initializer, in
+    // which the object is already in scope
+    fn complete_local(&mut self, name: &str, ty: &Type) {
+        if let Some(var) = self.local_varmap.last_mut().unwrap().get_mut(name) {
+            var.0 = ty.clone();
+        }
+    }
+
+    // the length of an array which the parser could not fold, held by its hidden variable;
+    // an integer constant expression makes it an array of that length (see resolve_type),
+    // and anything else a variable length array
+    fn check_array_len(&mut self, name: String, len: AST) -> AST {
+        let (len, ty) = self.check_value(len);
+        if !ty.is_integer() {
+            panic!("Size of array has non-integer type {}", ty);
+        }
+        match self.eval_const(&len) {
+            Ok(ConstValue::Int(val, _)) if val < 0 && !ty.is_unsigned() => panic!("Size of array is negative"),
+            Ok(ConstValue::Int(val, _)) => {
+                self.array_lens.insert(name.clone(), val);
+            }
+            Err(ConstError::Invalid(msg)) => panic!("Invalid size of array: {}", msg),
+            _ => (),
+        }
+        let len = self.convert(len, &ty, &Type::ULong);
+        AST::VariableDecl(Type::ULong, name, Some(Box::new(len)), VarAttr::default())
+    }
+
+    // a variable length array whose length turned out to be constant is an array, unless
+    // its elements are variable length arrays
+    fn resolve_type(&self, ty: Type) -> Type {
+        match ty {
+            Type::Vla(elem_ty, name) => {
+                let elem_ty = self.resolve_type(*elem_ty);
+                match self.array_lens.get(&name) {
+                    Some(&len) if !elem_ty.is_vla() => Type::Array(Box::new(elem_ty), len as i32),
+                    _ => Type::Vla(Box::new(elem_ty), name),
+                }
+            }
+            Type::Ptr(base_ty) => Type::Ptr(Box::new(self.resolve_type(*base_ty))),
+            Type::Qual(ty, quals) => Type::Qual(Box::new(self.resolve_type(*ty)), quals),
+            ty => ty,
+        }
+    }
+
+    // _Static_assert in a block or at file scope, which generates no code
+    fn fold_static_assert(&mut self, cond: AST, msg: &Option<Vec<u8>>) {
+        let (cond, _) = self.check_value(cond);
+        match self.eval_const(&cond).and_then(|val| val.to_int()) {
+            Ok(cond) => check_static_assert(cond, msg),
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    // the value of a checked expression folded at compile time, as required for sizes of
+    // arrays and initializers of global and static variables, or why it cannot be folded
+    fn eval_const(&self, ast: &AST) -> ConstResult {
+        match ast {
+            AST::Int(val, ty) => Ok(ConstValue::Int(const_eval::normalize(*val, ty), ty.clone())),
+            AST::Float(val, ty) => Ok(ConstValue::Float(*val, ty.clone())),
+            // a function designator, whose value is the address of the function
+            AST::Variable(_) => Ok(ConstValue::Addr(ast.clone())),
+            AST::Cast(expr, ty) => match self.eval_const(expr)? {
+                // an address can be converted to another pointer, or to an integer as wide as it
+                ConstValue::Addr(_) if ty.is_ptr() || ty.is_integer() && ty.size() == 8 => {
+                    Ok(ConstValue::Addr(ast.clone()))
+                }
+                val => const_eval::cast(val, ty),
+            },
+            AST::UnaryOp(expr, UnaryOps::Plus) => self.eval_const(expr),
+            AST::UnaryOp(expr, UnaryOps::Minus) => const_eval::negate(self.eval_const(expr)?),
+            AST::UnaryOp(lvalue, UnaryOps::Addr) => {
+                self.check_static_lvalue(lvalue)?;
+                Ok(ConstValue::Addr(ast.clone()))
+            }
+            AST::BinaryOp(lhs, rhs, op) => {
+                let lhs = self.eval_const(lhs)?;
+                let rhs = self.eval_const(rhs)?;
+                match (lhs, rhs, op) {
+                    (ConstValue::Int(lhs, ty), ConstValue::Int(rhs, _), _) if ty.is_integer() => {
+                        const_eval::int_binary_op(lhs, rhs, &ty, op)
+                    }
+                    (ConstValue::Float(lhs, ty), ConstValue::Float(rhs, _), _) => {
+                        const_eval::float_binary_op(lhs, rhs, &ty, op)
+                    }
+                    // an address plus or minus an integer, which codegen scales by the size
+                    // of the pointed-to type
+                    (ConstValue::Addr(_) | ConstValue::Int(..), ConstValue::Int(_, idx_ty), BinaryOps::Add | BinaryOps::Sub)
+                    | (ConstValue::Int(_, idx_ty), ConstValue::Addr(_), BinaryOps::Add)
+                        if idx_ty.is_integer() =>
+                    {
+                        Ok(ConstValue::Addr(ast.clone()))
+                    }
+                    // the difference of two addresses in the same object, which codegen folds
+                    (ConstValue::Addr(_) | ConstValue::Int(..), ConstValue::Addr(_) | ConstValue::Int(..), BinaryOps::Sub) => {
+                        Ok(ConstValue::Addr(ast.clone()))
+                    }
+                    // null pointers and integers converted to pointers compare as integers
+                    (ConstValue::Int(lhs, _), ConstValue::Int(rhs, _), BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le) => {
+                        const_eval::int_binary_op(lhs, rhs, &Type::ULong, op)
+                    }
+                    _ => Err(ConstError::NotConstant("Operation on addresses is not a constant expression".to_string())),
+                }
+            }
+            AST::Load(lvalue) => match &**lvalue {
+                AST::Variable(name) => Err(ConstError::NotConstant(format!("{} cannot be used in a constant expression", name))),
+                _ => Err(ConstError::NotConstant("Reading an object is not a constant expression".to_string())),
+            },
+            AST::FuncCall(..) => Err(ConstError::NotConstant("Function call in a constant expression".to_string())),
+            _ => Err(ConstError::NotConstant("Expression is not a constant expression".to_string())),
+        }
+    }
+
+    // an lvalue whose address is known at compile time, which designates an object with
+    // static storage duration
+    fn check_static_lvalue(&self, ast: &AST) -> Result<(), ConstError> {
+        match ast {
+            AST::Variable(name) => match self.lookup_local(name) {
+                Some((_, attr)) if !attr.is_static && !attr.is_extern => Err(ConstError::NotConstant(format!(
+                    "Address of local variable {} is not a constant",
+                    name
+                ))),
+                _ => Ok(()),
+            },
+            AST::Str(_) => Ok(()),
+            // a compound literal at file scope has static storage duration
+            AST::CompoundLiteral(_, init) if self.local_varmap.is_empty() => self.eval_const_init(init),
+            AST::Member(expr, _) => self.check_static_lvalue(expr),
+            AST::Index(ptr, idx) => {
+                self.eval_const(ptr)?;
+                self.eval_const(idx).map(|_| ())
+            }
+            AST::UnaryOp(ptr, UnaryOps::Deref) => self.eval_const(ptr).map(|_| ()),
+            _ => Err(ConstError::NotConstant("Address of an object with automatic storage is not a constant".to_string())),
+        }
+    }
+
+    // the address of a register variable cannot be taken, even implicitly by array decay
+    fn check_addressable(&self, lvalue: &AST) {
+        match lvalue {
+            AST::Variable(name) => {
+                if let Some((_, attr)) = self.lookup_local(name) {
+                    if attr.is_register {
+                        panic!("Address of register variable {} requested", name);
+                    }
+                }
+            }
+            AST::Member(expr, _) => self.check_addressable(expr),
+            _ => (),
+        }
+    }
+
+    fn eval_const_init(&self, init: &AST) -> Result<(), ConstError> {
+        match init {
+            AST::Initializer(leaves) => leaves
+                .iter()
+                .filter(|leaf| !matches!(leaf.expr, AST::Str(_)))
+                .try_for_each(|leaf| self.eval_const(&leaf.expr).map(|_| ())),
+            init => self.eval_const(init).map(|_| ()),
+        }
+    }
+
+    // an initializer of a global variable must consist of constant expressions, which are
+    // folded here except for addresses, which codegen folds
+    fn check_const_init(&mut self, init: AST, ty: &Type, name: &String) -> (AST, Type) {
+        let (init, ty) = self.check_initializer(ty, init);
+        let init = match init {
+            AST::Initializer(leaves) => AST::Initializer(
+                leaves
+                    .into_iter()
+                    .map(|leaf| match leaf.expr {
+                        AST::Str(_) => leaf,
+                        expr => InitLeaf {
+                            expr: self.fold_const(expr, name),
+                            ..leaf
+                        },
+                    })
+                    .collect(),
+            ),
+            init => self.fold_const(init, name),
+        };
+        (init, ty)
+    }
+
+    fn fold_const(&self, expr: AST, name: &String) -> AST {
+        match self.eval_const(&expr) {
+            Ok(ConstValue::Int(val, ty)) if ty.is_integer() => AST::Int(val, ty),
+            // long double keeps its expression, which codegen folds with its full precision
+            Ok(ConstValue::Float(val, ty)) if !matches!(ty, Type::LongDouble) => AST::Float(val, ty),
+            Ok(_) => expr,
+            Err(err) => panic!("Initializer element of {} is not a compile-time constant: {}", name, err),
+        }
+    }
+
+    // an initializer of an object of type ty, and the type completed by it; an aggregate is
+    // initialized by AST::Initializer, and anything else by an expression converted to ty
+    fn check_initializer(&mut self, ty: &Type, init: AST) -> (AST, Type) {
+        let tree = self.check_init_tree(ty, init);
+        let ty = match (ty, &tree) {
+            (Type::Array(elem_ty, -1), InitTree::Array(elems)) => Type::Array(elem_ty.clone(), elems.len() as i32),
+            (Type::Array(elem_ty, -1), InitTree::Str(s)) => Type::Array(elem_ty.clone(), s.len() as i32 + 1),
+            (Type::Array(elem_ty, -1), _) => Type::Array(elem_ty.clone(), 0),
+            _ => ty.clone(),
+        };
+        let init = match tree {
+            InitTree::Expr(expr) => expr,
+            InitTree::Zero if !is_aggregate(&ty) => self.convert(AST::Int(0, Type::Int), &Type::Int, &ty),
+            tree => {
+                let mut leaves = Vec::new();
+                flatten_init(&ty, tree, 0, &mut leaves);
+                AST::Initializer(leaves)
+            }
+        };
+        (init, ty)
+    }
+
+    fn check_init_tree(&mut self, ty: &Type, init: AST) -> InitTree {
+        let mut elems = match init {
+            AST::InitList(elems) => elems,
+            init if is_char_array(ty) && is_str_literal(&init) => return self.check_str_init(ty, init),
+            _ if matches!(ty, Type::Array(..)) => {
+                panic!("Array of type {} must be initialized by an initializer list", ty)
+            }
+            init => return InitTree::Expr(self.check_assign_conv(init, ty)),
+        };
+        if !is_aggregate(ty) {
+            // braces around a scalar initializer are allowed
+            if elems.is_empty() {
+                return InitTree::Zero;
+            }
+            if !elems[0].0.is_empty() {
+                panic!("Designator in initializer for scalar type {}", ty);
+            }
+            if elems.len() > 1 {
+                self.warn(Warning::ExcessInitializers, &format!("Excess elements in scalar initializer of {}", ty));
+            }
+            return self.check_init_tree(ty, elems.swap_remove(0).1);
+        }
+        // char s[] = {"abc"}
+        if is_char_array(ty) && elems.len() == 1 && elems[0].0.is_empty() && is_str_literal(&elems[0].1) {
+            return self.check_str_init(ty, elems.remove(0).1);
+        }
+        let mut tree = InitTree::new(ty);
+        let mut pos = 0;
+        self.init_aggregate(ty, &mut tree, 0, &mut elems, &mut pos, true);
+        tree
+    }
+
+    // a string literal initializes a char array; its terminating null character is dropped
+    // if the array has no room for it
+    fn check_str_init(&mut self, ty: &Type, init: AST) -> InitTree {
+        let mut s = match init {
+            AST::Load(lvalue) => match *lvalue {
+                AST::Str(s) => s,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        if let Type::Array(_, len) = ty {
+            if *len >= 0 && s.len() > *len as usize {
+                self.warn(Warning::ExcessInitializers, &format!("Initializer-string for {} is too long", ty));
+                s.truncate(*len as usize);
+            }
+        }
+        InitTree::Str(s)
+    }
+
+    // initialize the subobjects of an aggregate from the i-th one with elems[pos..].
+    // a braced list takes all of its elements, while a list whose braces are elided ends
+    // when the aggregate is full or at a designator, which belongs to the enclosing braces
+    fn init_aggregate(
+        &mut self,
+        ty: &Type,
+        tree: &mut InitTree,
+        mut i: usize,
+        elems: &mut [InitElem],
+        pos: &mut usize,
+        is_braced: bool,
+    ) {
+        if !tree.is_aggregate() {
+            *tree = InitTree::new(ty);
+        }
+        while *pos < elems.len() {
+            if !elems[*pos].0.is_empty() {
+                if !is_braced {
+                    return;
+                }
+                let designators = std::mem::take(&mut elems[*pos].0);
+                i = self.init_designated(ty, tree, &designators, elems, pos);
+                continue;
+            }
+            i = match next_subobject(ty, i) {
+                Some(i) => i,
+                None => {
+                    if is_braced {
+                        self.warn(Warning::ExcessInitializers, &format!("Excess elements in initializer of {}", ty));
+                        *pos = elems.len();
+                    }
+                    return;
+                }
+            };
+            let sub_ty = subobject_type(ty, i);
+            self.init_subobject(&sub_ty, tree.child(ty, i), elems, pos);
+            i += 1;
+        }
+    }
+
+    // initialize the subobject designated by the designators with elems[pos], and the ones
+    // after it as if their braces were elided; returns the index of the next subobject of ty
+    fn init_designated(
+        &mut self,
+        ty: &Type,
+        tree: &mut InitTree,
+        designators: &[Designator],
+        elems: &mut [InitElem],
+        pos: &mut usize,
+    ) -> usize {
+        if !tree.is_aggregate() {
+            *tree = InitTree::new(ty);
+        }
+        // a member of an anonymous struct or union is designated through the anonymous member
+        let (first, last, path) = match (&designators[0], ty.strip_qual()) {
+            (Designator::Index(first, last), Type::Array(_, len)) => {
+                if *len >= 0 && *last >= *len as i64 {
+                    panic!("Array index {} in initializer exceeds the bounds of {}", last, ty);
+                }
+                (*first as usize, *last as usize, Vec::new())
+            }
+            (Designator::Member(name), Type::Struct(st)) => match st.borrow().find_member(name) {
+                Some(path) => (path[0], path[0], path[1..].to_vec()),
+                None => panic!("No member named {} in {}", name, ty),
+            },
+            (Designator::Index(..), _) => panic!("Array designator used for non-array type {}", ty),
+            (Designator::Member(name), _) => {
+                panic!("Member designator .{} used for non-struct type {}", name, ty)
+            }
+        };
+        // every element in a range is initialized with the same initializer
+        let start = *pos;
+        for i in first..=last {
+            *pos = start;
+            let sub_ty = subobject_type(ty, i);
+            self.init_member_path(&sub_ty, tree.child(ty, i), &path, &designators[1..], elems, pos);
+        }
+        last + 1
+    }
+
+    fn init_member_path(
+        &mut self,
+        ty: &Type,
+        tree: &mut InitTree,
+        path: &[usize],
+        designators: &[Designator],
+        elems: &mut [InitElem],
+        pos: &mut usize,
+    ) {
+        if let Some((&i, path)) = path.split_first() {
+            if !tree.is_aggregate() {
+                *tree = InitTree::new(ty);
+            }
+            let sub_ty = subobject_type(ty, i);
+            self.init_member_path(&sub_ty, tree.child(ty, i), path, designators, elems, pos);
+            self.init_aggregate(ty, tree, i + 1, elems, pos, false);
+        } else if designators.is_empty() {
+            self.init_subobject(ty, tree, elems, pos);
+        } else {
+            let i = self.init_designated(ty, tree, designators, elems, pos);
+            self.init_aggregate(ty, tree, i, elems, pos, false);
+        }
+    }
+
+    // initialize a subobject with elems[pos]; an aggregate not initialized by a braced list,
+    // a string literal or a struct value takes the following elements as if braces were elided
+    fn init_subobject(&mut self, ty: &Type, tree: &mut InitTree, elems: &mut [InitElem], pos: &mut usize) {
+        let init = elems[*pos].1.clone();
+        if !matches!(init, AST::InitList(_)) && is_aggregate(ty) {
+            if is_char_array(ty) && is_str_literal(&init) {
+                *pos += 1;
+                *tree = self.check_str_init(ty, init);
+                return;
+            }
+            if ty.is_struct() {
+                let (expr, expr_ty) = self.check_value(init);
+                if expr_ty == ty.unqualified() {
+                    *pos += 1;
+                    *tree = InitTree::Expr(expr);
+                    return;
+                }
+            }
+            self.init_aggregate(ty, tree, 0, elems, pos, false);
+            return;
+        }
+        *pos += 1;
+        *tree = self.check_init_tree(ty, init);
+    }
+
+    fn check_func_def(&mut self, func_ty: Box<Type>, func_name: String, body: AST, mut attr: VarAttr) -> AST {
+        check_func_storage_class(&func_name, &attr);
+        attr.is_static = self.declare_linkage(&func_name, &attr, true) == Linkage::Internal;
+        let (ret_ty, param_types, param_names, is_variadic) = match &*func_ty {
+            Type::Func(ret_ty, param_types, param_names, is_variadic) => {
+                (ret_ty, param_types, param_names, *is_variadic)
+            }
+            _ => panic!("{} is not a function", func_name),
+        };
+        self.cur_ret_ty = Some((**ret_ty).clone());
+        self.cur_is_variadic = is_variadic;
+        self.enter_scope();
+        for (ty, name) in param_types.iter().zip(param_names.iter()) {
+            check_object_type(ty, name);
+            self.declare_local(name, ty, &VarAttr::default());
+        }
+        for unused in self.unused_locals.last_mut().unwrap() {
+            unused.1 = true;
+        }
+        // the outermost block of the body is in the same scope as the parameters
+        let body = match body {
+            AST::Block(block) => AST::Block(self.check_block_items(block)),
+            body => self.check_stmt(body),
+        };
+        self.leave_scope();
+        // main returns 0 when it reaches the end (C99 5.1.2.2.3)
+        if !ret_ty.is_void() && func_name != "main" && self.can_complete(&body) {
+            self.warn(Warning::ReturnType, &format!("Control reaches end of non-void function {}", func_name));
+        }
+        self.cur_ret_ty = None;
+        AST::FuncDef(func_ty, func_name, Box::new(body), attr)
+    }
+
+    fn check_stmt(&mut self, ast: AST) -> AST {
+        match ast {
+            AST::Block(block) => {
+                self.enter_scope();
+                let block = self.check_block_items(block);
+                self.leave_scope();
+                AST::Block(block)
+            }
+            AST::VariableDecl(ty, name, init_opt, mut attr) if attr.is_extern => {
+                // refers to the global variable, which may be defined in another translation unit
+                let ty = self.resolve_type(ty);
+                check_alignas(&ty, &name, &attr);
+                if init_opt.is_some() {
+                    panic!("{} has both extern and initializer", name);
+                }
+                if let Some(global_ty) = self.global_varmap.get(&name) {
+                    if composite_type(global_ty, &ty).is_none() {
+                        panic!("Conflicting types for {}", name);
+                    }
+                }
+                attr.is_static = self.declare_linkage(&name, &attr, false) == Linkage::Internal;
+                self.declare_thread_local(&name, &attr);
+                self.declare_local(&name, &ty, &attr);
+                AST::VariableDecl(ty, name, None, attr)
+            }
+            // a static local variable exists during the whole program,
+            // so it is initialized once with a constant
+            AST::VariableDecl(ty, name, init_opt, attr) if attr.is_static => {
+                let ty = self.resolve_type(ty);
+                if ty.is_vla() {
+                    panic!("Variable length array {} cannot have static storage duration", name);
+                }
+                self.declare_local(&name, &ty, &attr);
+                let (init_opt, ty) = match init_opt {
+                    Some(init) => {
+                        let (init, ty) = self.check_const_init(*init, &ty, &name);
+                        self.complete_local(&name, &ty);
+                        (Some(Box::new(init)), ty)
+                    }
+                    None => (None, ty),
+                };
+                check_object_type(&ty, &name);
+                check_alignas(&ty, &name, &attr);
+                AST::VariableDecl(ty, name, init_opt, attr)
+            }
+            AST::VariableDecl(ty, name, init_opt, attr) => {
+                let ty = self.resolve_type(ty);
+                if attr.is_thread_local {
+                    panic!("_Thread_local in block scope without static or extern: {}", name);
+                }
+                if ty.is_vla() && init_opt.is_some() {
+                    panic!("Variable-sized object {} may not be initialized", name);
+                }
+                self.declare_local(&name, &ty, &attr);
+                let (init_opt, ty) = match init_opt {
+                    Some(init) => {
+                        let (init, ty) = self.check_initializer(&ty, *init);
+                        self.complete_local(&name, &ty);
+                        (Some(Box::new(init)), ty)
+                    }
+                    None => (None, ty),
+                };
+                check_object_type(&ty, &name);
+                check_alignas(&ty, &name, &attr);
+                AST::VariableDecl(ty, name, init_opt, attr)
+            }
+            AST::ArrayLen(name, len) => {
+                let decl = self.check_array_len(name.clone(), *len);
+                self.declare_local(&name, &Type::ULong, &VarAttr::default());
+                decl
+            }
+            AST::StaticAssert(cond, msg) => {
+                self.fold_static_assert(*cond, &msg);
+                AST::Nil
+            }
+            AST::FuncDecl(func_ty, func_name, mut attr) => {
+                if attr.is_static {
+                    panic!("Invalid storage class for function {}", func_name);
+                }
+                check_func_storage_class(&func_name, &attr);
+                attr.is_static = self.declare_linkage(&func_name, &attr, true) == Linkage::Internal;
+                let func_ty = self.declare_func(&func_name, &func_ty);
+                self.declare_local(&func_name, &func_ty, &attr);
+                AST::FuncDecl(Box::new(func_ty), func_name, attr)
+            }
+            AST::Return(None) => {
+                if !self.cur_ret_ty.as_ref().unwrap().is_void() {
+                    panic!("Return without a value in function returning non-void");
+                }
+                AST::Return(None)
+            }
+            AST::Return(Some(expr)) => {
+                let ret_ty = self.cur_ret_ty.clone().unwrap();
+                if ret_ty.is_void() {
+                    panic!("Return with a value in function returning void");
+                }
+                AST::Return(Some(Box::new(self.check_assign_conv(*expr, &ret_ty))))
+            }
+            AST::If(cond, then, els) => AST::If(
+                Box::new(self.check_cond(*cond)),
+                Box::new(self.check_stmt(*then)),
+                Box::new(self.check_stmt(*els)),
+            ),
+            // variables declared in the init clause are in a scope enclosing the loop
+            AST::For(init, cond, step, body) => {
+                self.enter_scope();
+                let init = match *init {
+                    AST::Block(block) => AST::Block(self.check_block_items(block)),
+                    init => self.check_stmt(init),
+                };
+                let ast = AST::For(
+                    Box::new(init),
+                    Box::new(self.check_cond(*cond)),
+                    Box::new(self.check_stmt(*step)),
+                    Box::new(self.check_stmt(*body)),
+                );
+                self.leave_scope();
+                ast
+            }
+            AST::While(cond, body) => AST::While(
+                Box::new(self.check_cond(*cond)),
+                Box::new(self.check_stmt(*body)),
+            ),
+            AST::Nil => AST::Nil,
+            // expression statement
+            _ => self.check_expr(ast).0,
+        }
+    }
+
+    // the items of a block, in the current scope
+    fn check_block_items(&mut self, block: Vec<AST>) -> Vec<AST> {
+        let block: Vec<AST> = block.into_iter().map(|ast| self.check_stmt(ast)).collect();
+        // a declaration without an initializer after return does nothing, and is not reported
+        if let Some(i) = block.iter().position(|ast| !self.can_complete(ast)) {
+            if block[i + 1..].iter().any(|ast| !is_declaration_only(ast)) {
+                let after = match block[i] {
+                    AST::Return(_) => "return",
+                    AST::For(..) | AST::While(..) => "an infinite loop",
+                    AST::If(..) => "an if statement which never completes",
+                    _ => "a block which never completes",
+                };
+                self.warn(Warning::UnreachableCode, &format!("Code after {} is unreachable", after));
+            }
+        }
+        block
+    }
+
+    // whether execution can continue after the checked statement; ironcc does not support
+    // break, continue, goto or switch, so only return and loops whose condition is always
+    // true do not complete, and a loop never exits other than by its condition
+    fn can_complete(&self, ast: &AST) -> bool {
+        match ast {
+            AST::Return(_) => false,
+            AST::Block(block) => block.iter().all(|ast| self.can_complete(ast)),
+            AST::If(cond, then, els) => match self.const_cond(cond) {
+                Some(true) => self.can_complete(then),
+                Some(false) => self.can_complete(els),
+                None => self.can_complete(then) || self.can_complete(els),
+            },
+            AST::For(_, cond, _, _) | AST::While(cond, _) => self.const_cond(cond) != Some(true),
+            _ => true,
+        }
+    }
+
+    // the value of a checked condition which is a constant expression; no condition
+    // (e.g. for(;;)) is always true
+    fn const_cond(&self, cond: &AST) -> Option<bool> {
+        match cond {
+            AST::Nil => Some(true),
+            _ => self.eval_const(cond).ok().map(|val| !val.is_zero()),
+        }
+    }
+
+    // controlling expressions of if and loops must have scalar type
+    fn check_cond(&mut self, ast: AST) -> AST {
+        // no condition (e.g. for(;;)) means always true
+        if let AST::Nil = ast {
+            return AST::Nil;
+        }
+        // parentheses are not kept in the AST, so unlike GCC an extra pair does not silence it
+        if let AST::BinaryOp(_, _, BinaryOps::Assign) = ast {
+            self.warn(Warning::Parentheses, "Assignment used as a condition");
+        }
+        let (ast, ty) = self.check_value(ast);
+        if !ty.is_scalar() {
+            panic!("Scalar type is expected in a condition, but found {}", ty);
+        }
+        ast
+    }
+
+    // conversion as if by assignment, used for initializers, arguments and return values
+    fn check_assign_conv(&mut self, ast: AST, to: &Type) -> AST {
+        let (ast, ty) = self.check_value(ast);
+        self.convert(ast, &ty, to)
+    }
+
+    fn convert(&self, ast: AST, from: &Type, to: &Type) -> AST {
+        let to = to.strip_qual();
+        if from == to {
+            return ast;
+        }
+        // a pointer may gain qualifiers of the pointed-to type, but not lose them
+        if is_same_pointee(from, to) || is_void_ptr_conversion(from, to) {
+            if let (Type::Ptr(from_base), Type::Ptr(to_base)) = (from, to) {
+                if !from_base.qualifiers().is_subset_of(&to_base.qualifiers()) {
+                    self.warn(Warning::DiscardedQualifiers, &format!("Conversion from {} to {} discards qualifiers", from, to));
+                }
+            }
+            return AST::Cast(Box::new(ast), to.clone());
+        }
+        let is_convertible = from.is_arith() && to.is_arith()
+            || from.is_ptr() && to.is_bool()
+            || to.is_ptr() && is_null_pointer_constant(&ast);
+        if !is_convertible {
+            panic!("Cannot convert {} to {}", from, to);
+        }
+        AST::Cast(Box::new(ast), to.clone())
+    }
+
+    fn promote(&self, ast: AST, ty: &Type) -> (AST, Type) {
+        let promoted_ty = ty.promoted();
+        (self.convert(ast, ty, &promoted_ty), promoted_ty)
+    }
+
+    fn warn(&self, warning: Warning, msg: &str) {
+        if !self.options.warnings.is_enabled(warning) {
+            return;
+        }
+        if self.options.warnings.is_error(warning) {
+            panic!("{} [-Werror={}]", msg, warning.name());
+        }
+        eprintln!("warning: {} [-W{}]", msg, warning.name());
+    }
+
+    fn enter_scope(&mut self) {
+        self.local_varmap.push(HashMap::new());
+        self.unused_locals.push(Vec::new());
+    }
+
+    // the variables of the scope which have not been used are reported when it ends
+    fn leave_scope(&mut self) {
+        self.local_varmap.pop();
+        for (name, is_param) in self.unused_locals.pop().unwrap() {
+            if is_param {
+                self.warn(Warning::UnusedParameter, &format!("Unused parameter {}", name));
+            } else {
+                self.warn(Warning::UnusedVariable, &format!("Unused variable {}", name));
+            }
+        }
+    }
+
+    fn mark_used(&mut self, name: &String) {
+        if let Some(i) = self.local_varmap.iter().rposition(|scope| scope.contains_key(name)) {
+            if let Some(unused) = self.unused_locals.get_mut(i) {
+                unused.retain(|(unused_name, _)| unused_name != name);
+            }
+        }
+    }
+
+    fn lookup_local(&self, name: &String) -> Option<&(Type, VarAttr)> {
+        self.local_varmap.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup_var(&self, name: &String) -> Type {
+        if let Some((ty, _)) = self.lookup_local(name) {
+            return ty.clone();
+        }
+        if let Some(ty) = self.global_varmap.get(name) {
+            return ty.clone();
+        }
+        panic!("Use of undeclared identifier {}", name);
+    }
+
+    // check an expression whose value is used, which must not be void
+    fn check_value(&mut self, ast: AST) -> (AST, Type) {
+        let (ast, ty) = self.check_expr(ast);
+        if ty.is_void() {
+            panic!("void value not ignored as it ought to be");
+        }
+        (ast, ty)
+    }
+
+    fn check_expr(&mut self, ast: AST) -> (AST, Type) {
+        match ast {
+            AST::Int(n, ty) => (AST::Int(n, ty.clone()), ty),
+            AST::Float(f, ty) => (AST::Float(f, ty.clone()), ty),
+            // member of a struct which is not an lvalue (e.g. returned from a function)
+            AST::Member(expr, name) if !is_lvalue(&expr) => {
+                let (expr, ty) = self.check_value(*expr);
+                let (ty, bit_field) = member_type(&ty, &name);
+                load_bit_field(AST::Member(Box::new(expr), name), ty.unqualified(), bit_field)
+            }
+            // the hidden variable holding the length of an array, which turned out to be constant
+            AST::Load(ref lvalue) if self.folded_array_len(lvalue).is_some() => {
+                (AST::Int(self.folded_array_len(lvalue).unwrap(), Type::ULong), Type::ULong)
+            }
+            // lvalue conversion
+            AST::Load(_)
+            | AST::Variable(_)
+            | AST::Str(_)
+            | AST::CompoundLiteral(..)
+            | AST::UnaryOp(_, UnaryOps::Deref)
+            | AST::Member(..)
+            | AST::Index(..) => {
+                let (lvalue, ty, bit_field) = self.check_lvalue_bit_field(ast);
+                // an lvalue of type void (e.g. *p where p is void *) has no value to load
+                if ty.is_void() {
+                    return (lvalue, ty);
+                }
+                // a function designator is converted to the address of the function
+                if let Type::Func(..) = ty {
+                    return (lvalue, Type::Ptr(Box::new(ty)));
+                }
+                // an array is converted to a pointer to its first element
+                if let Type::Array(elem_ty, _) | Type::Vla(elem_ty, _) = ty {
+                    self.check_addressable(&lvalue);
+                    let addr = AST::UnaryOp(Box::new(lvalue), UnaryOps::Addr);
+                    let ptr_ty = Type::Ptr(elem_ty);
+                    return (AST::Cast(Box::new(addr), ptr_ty.clone()), ptr_ty);
+                }
+                // the value of an lvalue does not have the qualifiers of the lvalue
+                load_bit_field(lvalue, ty.unqualified(), bit_field)
+            }
+            AST::Unevaluated(query, operand) => self.check_unevaluated(query, *operand),
+            AST::Cast(expr, ty) => self.check_cast(*expr, ty),
+            AST::UnaryOp(expr, op) => self.check_unary_op(*expr, op),
+            AST::BinaryOp(lhs, rhs, op) => self.check_binary_op(*lhs, *rhs, op),
+            AST::FuncCall(callee, args) => self.check_func_call(*callee, args),
+            AST::VaStart(ap) => {
+                if !self.cur_is_variadic {
+                    panic!("va_start used in function with fixed parameters");
+                }
+                (AST::VaStart(Box::new(self.check_va_list(*ap))), Type::Void)
+            }
+            AST::VaArg(ap, ty) => self.check_va_arg(*ap, ty),
+            AST::VaEnd(ap) => (AST::VaEnd(Box::new(self.check_va_list(*ap))), Type::Void),
+            AST::VaCopy(dest, src) => {
+                let dest = self.check_va_list(*dest);
+                let src = self.check_va_list(*src);
+                (AST::VaCopy(Box::new(dest), Box::new(src)), Type::Void)
+            }
+            _ => panic!("Expression is expected, but found {:?}", ast),
+        }
+    }
+
+    fn check_lvalue(&mut self, ast: AST) -> (AST, Type) {
+        match ast {
+            AST::Load(expr) => self.check_lvalue(*expr),
+            AST::Variable(name) => {
+                let ty = self.lookup_var(&name);
+                self.mark_used(&name);
+                (AST::Variable(name), ty)
+            }
+            // a string literal is an array of char, including the terminating null character
+            AST::Str(s) => {
+                let len = s.len() as i32 + 1;
+                (AST::Str(s), Type::Array(Box::new(Type::Char), len))
+            }
+            // an unnamed object initialized by the initializer list
+            AST::CompoundLiteral(ty, init) => {
+                let (init, ty) = self.check_initializer(&ty, *init);
+                (AST::CompoundLiteral(ty.clone(), Box::new(init)), ty)
+            }
+            AST::UnaryOp(expr, UnaryOps::Deref) => {
+                let (expr, ty) = self.check_value(*expr);
+                match ty {
+                    Type::Ptr(base_ty) => (AST::UnaryOp(Box::new(expr), UnaryOps::Deref), *base_ty),
+                    _ => panic!("Cannot dereference {}", ty),
+                }
+            }
+            AST::Member(..) => {
+                let (lvalue, ty, _) = self.check_lvalue_bit_field(ast);
+                (lvalue, ty)
+            }
+            // x[y] designates the y-th element from the pointer x (or x-th one from y)
+            AST::Index(lhs, rhs) => {
+                let (lhs, lhs_ty) = self.check_value(*lhs);
+                let (rhs, rhs_ty) = self.check_value(*rhs);
+                let (ptr, ptr_ty, idx, idx_ty) = if lhs_ty.is_integer() {
+                    (rhs, rhs_ty, lhs, lhs_ty)
+                } else {
+                    (lhs, lhs_ty, rhs, rhs_ty)
+                };
+                let elem_ty = match ptr_ty {
+                    Type::Ptr(elem_ty) if elem_ty.is_complete() => *elem_ty,
+                    _ => panic!("Subscripted value of type {} is not an array or pointer", ptr_ty),
+                };
+                if !idx_ty.is_integer() {
+                    panic!("Array subscript of type {} is not an integer", idx_ty);
+                }
+                let idx = self.convert(idx, &idx_ty, &Type::Long);
+                (AST::Index(Box::new(ptr), Box::new(idx)), elem_ty)
+            }
+            _ => panic!("lvalue is required"),
+        }
+    }
+
+    fn folded_array_len(&self, lvalue: &AST) -> Option<i64> {
+        match lvalue {
+            AST::Variable(name) => self.array_lens.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    // sizeof, _Alignof and _Generic depend only on the type of the operand, which is not
+    // evaluated; the variables in it still count as used
+    fn check_unevaluated(&mut self, query: TypeQuery, operand: AST) -> (AST, Type) {
+        let (op, is_sizeof) = match query {
+            // the type of the controlling expression after the conversion of an lvalue to its
+            // value, of an array to a pointer and of a function to its address
+            TypeQuery::Generic(assocs, default) => {
+                let (_, ctrl_ty) = self.check_expr(operand);
+                let selected = assocs
+                    .into_iter()
+                    .find(|(ty, _)| is_compatible(&ctrl_ty, ty))
+                    .map(|(_, expr)| expr);
+                return match selected.or(default.map(|expr| *expr)) {
+                    Some(expr) => self.check_expr(expr),
+                    None => panic!("_Generic has no association compatible with {}", ctrl_ty),
+                };
+            }
+            TypeQuery::SizeOf => ("sizeof", true),
+            TypeQuery::AlignOf => ("_Alignof", false),
+        };
+        // no conversion of arrays and functions to pointers
+        let ty = if is_lvalue(&operand) {
+            let (_, ty, bit_field) = self.check_lvalue_bit_field(operand);
+            if bit_field.is_some() {
+                panic!("Invalid application of {} to a bit-field", op);
+            }
+            ty
+        } else {
+            self.check_expr(operand).1
+        };
+        if !ty.is_complete() || ty.is_func() {
+            panic!("Invalid application of {} to type {}", op, ty);
+        }
+        if is_sizeof {
+            self.check_expr(sizeof_expr(&ty))
+        } else {
+            (AST::Int(ty.align() as i64, Type::ULong), Type::ULong)
+        }
+    }
+
+    // check an lvalue which may designate a bit-field
+    fn check_lvalue_bit_field(&mut self, ast: AST) -> (AST, Type, Option<BitField>) {
+        match ast {
+            AST::Member(expr, name) => {
+                let (expr, ty) = self.check_lvalue(*expr);
+                let (ty, bit_field) = member_type(&ty, &name);
+                (AST::Member(Box::new(expr), name), ty, bit_field)
+            }
+            _ => {
+                let (lvalue, ty) = self.check_lvalue(ast);
+                (lvalue, ty, None)
+            }
+        }
+    }
+
+    fn check_unary_op(&mut self, ast: AST, op: UnaryOps) -> (AST, Type) {
+        match op {
+            UnaryOps::Plus | UnaryOps::Minus => {
+                let (ast, ty) = self.check_value(ast);
+                if !ty.is_arith() {
+                    panic!("Invalid argument type {} to unary expression", ty);
+                }
+                let (ast, ty) = self.promote(ast, &ty);
+                match op {
+                    UnaryOps::Plus => (ast, ty),
+                    _ => (AST::UnaryOp(Box::new(ast), op), ty),
+                }
+            }
+            UnaryOps::Addr => {
+                let (lvalue, ty, bit_field) = self.check_lvalue_bit_field(ast);
+                if bit_field.is_some() {
+                    panic!("Cannot take address of bit-field");
+                }
+                self.check_addressable(&lvalue);
+                (
+                    AST::UnaryOp(Box::new(lvalue), UnaryOps::Addr),
+                    Type::Ptr(Box::new(ty)),
+                )
+            }
+            _ => panic!("Unsupported unary op"),
+        }
+    }
+
+    fn check_binary_op(&mut self, lhs: AST, rhs: AST, op: BinaryOps) -> (AST, Type) {
+        if let BinaryOps::Assign = op {
+            let (lvalue, ty) = self.check_lvalue(lhs);
+            if let Type::Array(..) | Type::Vla(..) | Type::Func(..) = ty {
+                panic!("{} is not assignable", ty);
+            }
+            if ty.is_const() {
+                panic!("Cannot assign to an lvalue of const-qualified type {}", ty);
+            }
+            if has_const_member(&ty) {
+                panic!("Cannot assign to {} with a const-qualified member", ty);
+            }
+            let rhs = self.check_assign_conv(rhs, &ty);
+            return (AST::BinaryOp(Box::new(lvalue), Box::new(rhs), op), ty.unqualified());
+        }
+
+        let (lhs, lhs_ty) = self.check_value(lhs);
+        let (rhs, rhs_ty) = self.check_value(rhs);
+
+        if lhs_ty.is_arith() && rhs_ty.is_arith() {
+            let ty = types::usual_arith_conv(&lhs_ty, &rhs_ty);
+            if ty.is_unsigned() && matches!(op, BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le) {
+                self.check_sign_compare(&lhs, &lhs_ty, &rhs, &rhs_ty);
+            }
+            let lhs = self.convert(lhs, &lhs_ty, &ty);
+            let rhs = self.convert(rhs, &rhs_ty, &ty);
+            // the result of comparison operators has type int
+            let ret_ty = match op {
+                BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le => Type::Int,
+                _ => ty,
+            };
+            return (AST::BinaryOp(Box::new(lhs), Box::new(rhs), op), ret_ty);
+        }
+
+        match op {
+            BinaryOps::Add | BinaryOps::Sub => self.check_ptr_arith(lhs, lhs_ty, rhs, rhs_ty, op),
+            BinaryOps::Eq | BinaryOps::Ne | BinaryOps::Lt | BinaryOps::Le => {
+                self.check_ptr_comparison(lhs, lhs_ty, rhs, rhs_ty, op)
+            }
+            _ => panic!(
+                "Invalid operands to binary expression ({} and {})",
+                lhs_ty, rhs_ty
+            ),
+        }
+    }
+
+    // pointer + integer, integer + pointer, pointer - integer and pointer - pointer.
+    // the pointer is always the lhs and the integer is converted to long in the result
+    fn check_ptr_arith(&mut self, lhs: AST, lhs_ty: Type, rhs: AST, rhs_ty: Type, op: BinaryOps) -> (AST, Type) {
+        let invalid = || -> ! {
+            panic!(
+                "Invalid operands to binary expression ({} and {})",
+                lhs_ty, rhs_ty
+            )
+        };
+        // void * is treated as char * as a GNU extension
+        let check_pointee = |ty: &Type| match ty {
+            Type::Ptr(base_ty) if base_ty.is_void() || base_ty.is_complete() && !matches!(**base_ty, Type::Func(..)) => (),
+            _ => panic!("Arithmetic on a pointer to an incomplete type {}", ty),
+        };
+        match op {
+            BinaryOps::Add | BinaryOps::Sub if lhs_ty.is_ptr() && rhs_ty.is_integer() => {
+                check_pointee(&lhs_ty);
+                let rhs = self.convert(rhs, &rhs_ty, &Type::Long);
+                (AST::BinaryOp(Box::new(lhs), Box::new(rhs), op), lhs_ty)
+            }
+            BinaryOps::Add if lhs_ty.is_integer() && rhs_ty.is_ptr() => {
+                check_pointee(&rhs_ty);
+                let lhs = self.convert(lhs, &lhs_ty, &Type::Long);
+                (AST::BinaryOp(Box::new(rhs), Box::new(lhs), op), rhs_ty)
+            }
+            // the difference of two pointers is the number of elements between them, as ptrdiff_t
+            BinaryOps::Sub if lhs_ty.is_ptr() && rhs_ty.is_ptr() => {
+                if !is_same_pointee(&lhs_ty, &rhs_ty) {
+                    panic!("{} and {} are not pointers to compatible types", lhs_ty, rhs_ty);
+                }
+                check_pointee(&lhs_ty);
+                (AST::BinaryOp(Box::new(lhs), Box::new(rhs), op), Type::Long)
+            }
+            _ => invalid(),
+        }
+    }
+
+    // a signed operand converted to unsigned for a comparison changes its value if it is
+    // negative, which it cannot be if it is a nonnegative constant
+    fn check_sign_compare(&self, lhs: &AST, lhs_ty: &Type, rhs: &AST, rhs_ty: &Type) {
+        let may_be_negative = |ast: &AST, ty: &Type| {
+            !ty.is_unsigned() && !matches!(self.eval_const(ast), Ok(ConstValue::Int(val, _)) if val >= 0)
+        };
+        if may_be_negative(lhs, lhs_ty) || may_be_negative(rhs, rhs_ty) {
+            self.warn(
+                Warning::SignCompare,
+                &format!("Comparison of integers of different signs: {} and {}", lhs_ty, rhs_ty),
+            );
+        }
+    }
+
+    // both operands are converted to the same pointer type
+    fn check_ptr_comparison(&mut self, lhs: AST, lhs_ty: Type, rhs: AST, rhs_ty: Type, op: BinaryOps) -> (AST, Type) {
+        let (lhs, rhs) = if lhs_ty.is_ptr() && rhs_ty.is_ptr() {
+            if is_same_pointee(&lhs_ty, &rhs_ty) {
+                (lhs, rhs)
+            } else if is_void_ptr_conversion(&lhs_ty, &rhs_ty) {
+                // compare as void * qualified like both pointed-to types
+                let quals = match (&lhs_ty, &rhs_ty) {
+                    (Type::Ptr(lhs_base), Type::Ptr(rhs_base)) => {
+                        types::Qualifiers {
+                            is_restrict: false,
+                            ..lhs_base.qualifiers().union(&rhs_base.qualifiers())
+                        }
+                    }
+                    _ => unreachable!(),
+                };
+                let void_ptr = Type::Ptr(Box::new(Type::Void.qualified(quals)));
+                (
+                    self.convert(lhs, &lhs_ty, &void_ptr),
+                    self.convert(rhs, &rhs_ty, &void_ptr),
+                )
+            } else {
+                panic!("Comparison of distinct pointer types ({} and {})", lhs_ty, rhs_ty);
+            }
+        } else if lhs_ty.is_ptr() && is_null_pointer_constant(&rhs) {
+            let rhs = self.convert(rhs, &rhs_ty, &lhs_ty);
+            (lhs, rhs)
+        } else if rhs_ty.is_ptr() && is_null_pointer_constant(&lhs) {
+            let lhs = self.convert(lhs, &lhs_ty, &rhs_ty);
+            (lhs, rhs)
+        } else {
+            panic!("Comparison between pointer and integer ({} and {})", lhs_ty, rhs_ty);
+        };
+        (AST::BinaryOp(Box::new(lhs), Box::new(rhs), op), Type::Int)
+    }
+
+    // explicit conversion by a cast operator
+    fn check_cast(&mut self, ast: AST, to: Type) -> (AST, Type) {
+        // the result of a cast is not an lvalue, so its qualifiers are meaningless
+        let to = to.unqualified();
+        // a cast to void discards the value, which may be of any type
+        if to.is_void() {
+            let (ast, _) = self.check_expr(ast);
+            return (AST::Cast(Box::new(ast), to.clone()), to);
+        }
+        if !to.is_scalar() {
+            panic!("Used type {} where arithmetic or pointer type is required", to);
+        }
+        let (ast, from) = self.check_value(ast);
+        if !from.is_scalar() {
+            panic!("Operand of type {} where arithmetic or pointer type is required", from);
+        }
+        if from.is_ptr() && to.is_flonum() || from.is_flonum() && to.is_ptr() {
+            panic!("Cannot cast {} to {}", from, to);
+        }
+        if from == to {
+            return (ast, to);
+        }
+        (AST::Cast(Box::new(ast), to.clone()), to)
+    }
+
+    // the callee is a function designator, which is converted to a pointer, or a function pointer
+    fn check_func_call(&mut self, callee: AST, args: Vec<AST>) -> (AST, Type) {
+        let callee_name = match &callee {
+            AST::Load(var) => match &**var {
+                AST::Variable(name) => name.clone(),
+                _ => "function pointer".to_string(),
+            },
+            _ => "function pointer".to_string(),
+        };
+        if self.is_undeclared_func(&callee) {
+            // an implicit declaration as in C89: int name();
+            self.warn(Warning::ImplicitFunctionDeclaration, &format!("Implicit declaration of function {}", callee_name));
+            let func_ty = Type::Func(Box::new(Type::Int), Vec::new(), Vec::new(), true);
+            self.declare_linkage(&callee_name, &VarAttr::default(), true);
+            self.declare_func(&callee_name, &func_ty);
+        }
+        let (callee, callee_ty) = self.check_value(callee);
+        // the function may be defined with a prototype, so the call is made through
+        // a pointer of the type without the prototype, as the arguments are passed
+        let callee = match &callee_ty {
+            Type::Ptr(func_ty) if func_ty.is_unprototyped() => AST::Cast(Box::new(callee), callee_ty.clone()),
+            _ => callee,
+        };
+        let (ret_ty, param_types, is_variadic) = match callee_ty {
+            Type::Ptr(func_ty) => match *func_ty {
+                Type::Func(ret_ty, param_types, _, is_variadic) => (*ret_ty, param_types, is_variadic),
+                ty => panic!("Called object type {} * is not a function or function pointer", ty),
+            },
+            ty => panic!("Called object type {} is not a function or function pointer", ty),
+        };
+        if args.len() < param_types.len() || args.len() > param_types.len() && !is_variadic {
+            panic!("Wrong number of arguments to {}", callee_name);
+        }
+        let mut checked_args = Vec::new();
+        for (i, arg) in args.into_iter().enumerate() {
+            match param_types.get(i) {
+                Some(param_ty) => checked_args.push(self.check_assign_conv(arg, param_ty)),
+                // arguments for ... only undergo the default argument promotions
+                None => {
+                    let (arg, ty) = self.check_value(arg);
+                    checked_args.push(self.default_promote(arg, &ty).0);
+                }
+            }
+        }
+        (AST::FuncCall(Box::new(callee), checked_args), ret_ty)
+    }
+
+    fn is_undeclared_func(&self, callee: &AST) -> bool {
+        match callee {
+            AST::Load(var) => match &**var {
+                AST::Variable(name) => self.lookup_local(name).is_none() && !self.global_varmap.contains_key(name),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn default_promote(&self, ast: AST, ty: &Type) -> (AST, Type) {
+        let promoted_ty = ty.default_promoted();
+        (self.convert(ast, ty, &promoted_ty), promoted_ty)
+    }
+
+    // a va_list argument of the builtins, which has been converted to a pointer to __va_list_tag
+    fn check_va_list(&mut self, ap: AST) -> AST {
+        let (ap, ty) = self.check_value(ap);
+        match &ty {
+            Type::Ptr(base_ty) if is_va_list_tag(base_ty) => ap,
+            _ => panic!("Expected va_list, but found {}", ty),
+        }
+    }
+
+    fn check_va_arg(&mut self, ap: AST, ty: Type) -> (AST, Type) {
+        let ap = self.check_va_list(ap);
+        let ty = ty.unqualified();
+        if !ty.is_complete() || matches!(ty, Type::Array(..) | Type::Vla(..) | Type::Func(..)) {
+            panic!("Invalid type {} for va_arg", ty);
+        }
+        // such an argument has been passed as the promoted type
+        let (_, promoted_ty) = self.default_promote(AST::Nil, &ty);
+        if promoted_ty != ty {
+            self.warn(Warning::Varargs, &format!("{} is promoted to {} when passed through ...", ty, promoted_ty));
+        }
+        (AST::VaArg(Box::new(ap), ty.clone()), ty)
+    }
+}
@@ -1,7 +1,583 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
 #[derive(Debug, Clone)]
 pub enum Type {
+    Void,
+    Bool,
+    Char,
+    UChar,
+    Short,
+    UShort,
     Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+    Float,
+    Double,
+    LongDouble,
     Ptr(Box<Type>),
-    Array(Box<Type>, i32),                   // type, size
-    Func(Box<Type>, Vec<Type>, Vec<String>), // ret type, param types, param names
+    Array(Box<Type>, i32),                   // type, size (-1 if unknown, e.g. flexible array member)
+    Func(Box<Type>, Vec<Type>, Vec<String>, bool), // ret type, param types, param names, is variadic
+    // variable length array: element type, and the name of the hidden local variable that holds
+    // the number of elements, evaluated when the declarator is reached
+    Vla(Box<Type>, String),
+    // struct and union types are shared so that an incomplete type can be completed later
+    Struct(Rc<RefCell<StructDef>>),
+    // qualified type, which is never nested, never an array (whose elements are qualified
+    // instead) and never has empty qualifiers
+    Qual(Box<Type>, Qualifiers),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+    pub is_atomic: bool,
+}
+
+impl Qualifiers {
+    pub fn is_empty(&self) -> bool {
+        *self == Qualifiers::default()
+    }
+
+    pub fn union(&self, other: &Qualifiers) -> Qualifiers {
+        Qualifiers {
+            is_const: self.is_const || other.is_const,
+            is_volatile: self.is_volatile || other.is_volatile,
+            is_restrict: self.is_restrict || other.is_restrict,
+            is_atomic: self.is_atomic || other.is_atomic,
+        }
+    }
+
+    // whether every qualifier in self is also in other
+    pub fn is_subset_of(&self, other: &Qualifiers) -> bool {
+        self.union(other) == *other
+    }
+}
+
+impl fmt::Display for Qualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = Vec::new();
+        if self.is_const {
+            names.push("const");
+        }
+        if self.is_volatile {
+            names.push("volatile");
+        }
+        if self.is_restrict {
+            names.push("restrict");
+        }
+        if self.is_atomic {
+            names.push("_Atomic");
+        }
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: Option<String>, // None for an anonymous struct or union member
+    pub ty: Type,
+    pub offset: i32, // for a bit-field, the offset of its storage unit
+    pub bit_field: Option<BitField>,
+}
+
+// a bit-field occupies `width` bits from `offset`-th bit of a storage unit of its type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitField {
+    pub offset: i32,
+    pub width: i32,
+}
+
+// the data model of the target, from which sizes and alignments of types are computed
+pub struct Target {
+    pub triple: &'static str,
+    pub data_layout: &'static str,
+    pub ptr_size: i32,
+    pub long_size: i32,
+    pub long_double_size: i32,
+    pub long_double_align: i32,
+}
+
+// x86-64 Linux (LP64); long double is the x87 80-bit format padded to 16 bytes
+pub const TARGET: Target = Target {
+    triple: "x86_64-pc-linux-gnu",
+    data_layout: "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128",
+    ptr_size: 8,
+    long_size: 8,
+    long_double_size: 16,
+    long_double_align: 16,
+};
+
+// a member declaration: name, type, width if it is a bit-field and alignment by _Alignas
+pub type MemberDecl = (Option<String>, Type, Option<i32>, Option<i32>);
+
+pub struct StructDef {
+    pub id: usize, // unique for each struct or union declaration
+    pub tag: Option<String>,
+    pub is_union: bool,
+    pub is_complete: bool,
+    pub members: Vec<Member>,
+    pub size: i32,
+    pub align: i32,
+}
+
+pub fn align_to(n: i32, align: i32) -> i32 {
+    (n + align - 1) / align * align
+}
+
+impl StructDef {
+    pub fn new(id: usize, tag: Option<String>, is_union: bool) -> StructDef {
+        StructDef {
+            id,
+            tag,
+            is_union,
+            is_complete: false,
+            members: Vec::new(),
+            size: 0,
+            align: 1,
+        }
+    }
+
+    // complete the type, laying out the members as the SysV x86-64 ABI does
+    pub fn set_members(&mut self, members: Vec<MemberDecl>) {
+        let member_cnt = members.len();
+        // the layout is tracked in bits since bit-fields can share bytes
+        let mut bits = 0;
+        for (i, (name, ty, width, align)) in members.into_iter().enumerate() {
+            if let Some(width) = width {
+                if align.is_some() {
+                    panic!("_Alignas cannot be specified for bit-field {}", name.unwrap_or_default());
+                }
+                self.add_bit_field(name, ty, width, &mut bits);
+                continue;
+            }
+            let is_flexible = matches!(ty, Type::Array(_, -1));
+            if is_flexible && (i != member_cnt - 1 || member_cnt == 1) {
+                panic!("Flexible array member must be the last member of a struct with other members");
+            }
+            if !is_flexible && !ty.is_complete() {
+                panic!("Member has incomplete type {}", ty);
+            }
+            let align = match align {
+                Some(align) if align != 0 && align < ty.align() => {
+                    panic!("_Alignas cannot reduce the alignment of member {} below {}", name.unwrap_or_default(), ty.align())
+                }
+                Some(align) => ty.align().max(align),
+                None => ty.align(),
+            };
+            let member_offset = if self.is_union {
+                0
+            } else {
+                align_to(align_to(bits, 8) / 8, align)
+            };
+            let end = member_offset + ty.size();
+            if !self.is_union {
+                bits = end * 8;
+            }
+            self.size = self.size.max(end);
+            self.align = self.align.max(align);
+            self.members.push(Member {
+                name,
+                ty,
+                offset: member_offset,
+                bit_field: None,
+            });
+        }
+        // tail padding
+        self.size = align_to(self.size, self.align);
+        self.is_complete = true;
+    }
+
+    // place a bit-field as GCC does: it is packed right after the previous member
+    // unless it would straddle a boundary of a storage unit of its type
+    fn add_bit_field(&mut self, name: Option<String>, ty: Type, width: i32, bits: &mut i32) {
+        if !ty.is_integer() {
+            panic!("Bit-field {} has non-integer type {}", name.unwrap_or_default(), ty);
+        }
+        let unit_bits = ty.size() * 8;
+        if width < 0 || width > unit_bits {
+            panic!("Width of bit-field {} exceeds its type {}", name.unwrap_or_default(), ty);
+        }
+        if ty.is_bool() && width > 1 {
+            panic!("Width of bit-field {} exceeds its type {}", name.unwrap_or_default(), ty);
+        }
+        let start = if self.is_union { 0 } else { *bits };
+        if width == 0 {
+            if let Some(name) = name {
+                panic!("Zero width for bit-field {}", name);
+            }
+            // the next member starts at the next storage unit
+            *bits = align_to(start, unit_bits);
+            self.size = self.size.max(align_to(*bits, 8) / 8);
+            return;
+        }
+        let start = if start / unit_bits != (start + width - 1) / unit_bits {
+            align_to(start, unit_bits)
+        } else {
+            start
+        };
+        let end = start + width;
+        if !self.is_union {
+            *bits = end;
+        }
+        self.size = self.size.max(align_to(end, 8) / 8);
+        // an unnamed bit-field is only padding, and does not affect the alignment
+        if let Some(name) = name {
+            let unit_offset = start / unit_bits * ty.size();
+            self.align = self.align.max(ty.align());
+            self.members.push(Member {
+                name: Some(name),
+                ty,
+                offset: unit_offset,
+                bit_field: Some(BitField {
+                    offset: start - unit_offset * 8,
+                    width,
+                }),
+            });
+        }
+    }
+
+    // returns the indices of the members to follow to reach the named member,
+    // looking into anonymous struct and union members
+    pub fn find_member(&self, name: &str) -> Option<Vec<usize>> {
+        for (i, member) in self.members.iter().enumerate() {
+            match (&member.name, &member.ty) {
+                (Some(member_name), _) if member_name == name => return Some(vec![i]),
+                (None, Type::Struct(st)) => {
+                    if let Some(mut path) = st.borrow().find_member(name) {
+                        path.insert(0, i);
+                        return Some(path);
+                    }
+                }
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+// parameter names are not part of a function type
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Ptr(base), Type::Ptr(other_base)) => base == other_base,
+            (Type::Array(elem, len), Type::Array(other_elem, other_len)) => {
+                elem == other_elem && len == other_len
+            }
+            // the lengths are only known at run time
+            (Type::Vla(elem, _), Type::Vla(other_elem, _)) => elem == other_elem,
+            // qualifiers of parameters do not belong to the function type
+            (Type::Func(ret, params, _, is_variadic), Type::Func(other_ret, other_params, _, other_is_variadic)) => {
+                ret == other_ret
+                    && is_variadic == other_is_variadic
+                    && params.len() == other_params.len()
+                    && params
+                        .iter()
+                        .zip(other_params.iter())
+                        .all(|(param, other_param)| param.unqualified() == other_param.unqualified())
+            }
+            (Type::Struct(st), Type::Struct(other_st)) => st == other_st,
+            (Type::Qual(ty, quals), Type::Qual(other_ty, other_quals)) => {
+                ty == other_ty && quals == other_quals
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+// two struct types are the same only if they come from the same declaration
+impl PartialEq for StructDef {
+    fn eq(&self, other: &StructDef) -> bool {
+        self.id == other.id
+    }
+}
+
+// members are not printed since they can refer to the struct itself
+impl fmt::Debug for StructDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StructDef({}, {:?})", self.id, self.tag)
+    }
+}
+
+impl Type {
+    // the type with the qualifiers added; qualifiers of an array type apply to its elements
+    pub fn qualified(self, quals: Qualifiers) -> Type {
+        if quals.is_empty() {
+            return self;
+        }
+        match self {
+            Type::Array(elem_ty, len) => Type::Array(Box::new(elem_ty.qualified(quals)), len),
+            Type::Vla(elem_ty, len) => Type::Vla(Box::new(elem_ty.qualified(quals)), len),
+            Type::Qual(ty, prev_quals) => Type::Qual(ty, prev_quals.union(&quals)),
+            // qualifiers on a function type (through a typedef) have no effect
+            Type::Func(..) => self,
+            _ => {
+                if quals.is_restrict && !self.is_ptr() {
+                    panic!("restrict requires a pointer, but found {}", self);
+                }
+                Type::Qual(Box::new(self), quals)
+            }
+        }
+    }
+
+    pub fn strip_qual(&self) -> &Type {
+        match self {
+            Type::Qual(ty, _) => ty,
+            _ => self,
+        }
+    }
+
+    // the type of the value of an lvalue of this type
+    pub fn unqualified(&self) -> Type {
+        self.strip_qual().clone()
+    }
+
+    pub fn qualifiers(&self) -> Qualifiers {
+        match self {
+            Type::Qual(_, quals) => *quals,
+            Type::Array(elem_ty, _) | Type::Vla(elem_ty, _) => elem_ty.qualifiers(),
+            _ => Qualifiers::default(),
+        }
+    }
+
+    pub fn is_const(&self) -> bool {
+        self.qualifiers().is_const
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.int_rank() > 0
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self.strip_qual(),
+            Type::Bool
+                | Type::UChar | Type::UShort | Type::UInt | Type::ULong | Type::ULongLong
+        )
+    }
+
+    pub fn is_flonum(&self) -> bool {
+        matches!(self.strip_qual(), Type::Float | Type::Double | Type::LongDouble)
+    }
+
+    pub fn is_arith(&self) -> bool {
+        self.is_integer() || self.is_flonum()
+    }
+
+    pub fn is_void(&self) -> bool {
+        matches!(self.strip_qual(), Type::Void)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self.strip_qual(), Type::Bool)
+    }
+
+    pub fn is_ptr(&self) -> bool {
+        matches!(self.strip_qual(), Type::Ptr(_))
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        self.is_arith() || self.is_ptr()
+    }
+
+    pub fn is_vla(&self) -> bool {
+        matches!(self, Type::Vla(..))
+    }
+
+    pub fn is_func(&self) -> bool {
+        matches!(self, Type::Func(..))
+    }
+
+    // a function declared without a prototype (e.g. int f();) takes any arguments,
+    // so it is represented as a variadic function without named parameters
+    pub fn is_unprototyped(&self) -> bool {
+        matches!(self, Type::Func(_, param_types, _, true) if param_types.is_empty())
+    }
+
+    pub fn is_struct(&self) -> bool {
+        matches!(self.strip_qual(), Type::Struct(_))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Type::Void => false,
+            Type::Array(elem_ty, len) => *len >= 0 && elem_ty.is_complete(),
+            Type::Vla(elem_ty, _) => elem_ty.is_complete(),
+            Type::Struct(st) => st.borrow().is_complete,
+            Type::Qual(ty, _) => ty.is_complete(),
+            _ => true,
+        }
+    }
+
+    // integer conversion rank (C11 6.3.1.1), 0 for non-integer types
+    pub fn int_rank(&self) -> i32 {
+        match self.strip_qual() {
+            Type::Bool => 1,
+            Type::Char | Type::UChar => 2,
+            Type::Short | Type::UShort => 3,
+            Type::Int | Type::UInt => 4,
+            Type::Long | Type::ULong => 5,
+            Type::LongLong | Type::ULongLong => 6,
+            _ => 0,
+        }
+    }
+
+    // rank used to pick the common type of two floating operands
+    pub fn flonum_rank(&self) -> i32 {
+        match self.strip_qual() {
+            Type::Float => 1,
+            Type::Double => 2,
+            Type::LongDouble => 3,
+            _ => 0,
+        }
+    }
+
+    // size in bytes on x86-64
+    pub fn size(&self) -> i32 {
+        match self {
+            // GNU extension: sizeof(void) is 1, which also makes arithmetic on void * work
+            Type::Void => 1,
+            Type::Bool | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float => 4,
+            Type::Long | Type::ULong => TARGET.long_size,
+            Type::LongLong | Type::ULongLong | Type::Double => 8,
+            Type::LongDouble => TARGET.long_double_size,
+            Type::Ptr(_) => TARGET.ptr_size,
+            // an array of unknown size occupies no storage (e.g. flexible array member)
+            Type::Array(elem_ty, len) => elem_ty.size() * (*len).max(0),
+            Type::Vla(..) => panic!("Variable length array has no constant size"),
+            Type::Func(..) => panic!("Function type has no size"),
+            Type::Struct(st) => st.borrow().size,
+            Type::Qual(ty, _) => ty.size(),
+        }
+    }
+
+    // alignment in bytes on the target
+    pub fn align(&self) -> i32 {
+        match self {
+            Type::LongDouble => TARGET.long_double_align,
+            Type::Array(elem_ty, _) | Type::Vla(elem_ty, _) => elem_ty.align(),
+            Type::Struct(st) => st.borrow().align,
+            Type::Func(..) => 1,
+            Type::Qual(ty, _) => ty.align(),
+            _ => self.size(),
+        }
+    }
+
+    pub fn to_unsigned(&self) -> Type {
+        match self {
+            Type::Char => Type::UChar,
+            Type::Short => Type::UShort,
+            Type::Int => Type::UInt,
+            Type::Long => Type::ULong,
+            Type::LongLong => Type::ULongLong,
+            _ => self.clone(),
+        }
+    }
+
+    // default argument promotions, applied to arguments without a parameter type:
+    // integer promotion, and float is promoted to double
+    pub fn default_promoted(&self) -> Type {
+        match self.strip_qual() {
+            Type::Float => Type::Double,
+            ty => ty.promoted(),
+        }
+    }
+
+    // integer promotion: every type whose rank is lower than int is converted to int,
+    // since int can represent all of their values
+    pub fn promoted(&self) -> Type {
+        if self.is_integer() && self.int_rank() < Type::Int.int_rank() {
+            Type::Int
+        } else {
+            self.clone()
+        }
+    }
+}
+
+// usual arithmetic conversions (C11 6.3.1.8)
+pub fn usual_arith_conv(lhs: &Type, rhs: &Type) -> Type {
+    if lhs.is_flonum() || rhs.is_flonum() {
+        return if lhs.flonum_rank() >= rhs.flonum_rank() {
+            lhs.clone()
+        } else {
+            rhs.clone()
+        };
+    }
+    let lhs = lhs.promoted();
+    let rhs = rhs.promoted();
+    if lhs == rhs {
+        return lhs;
+    }
+    if lhs.is_unsigned() == rhs.is_unsigned() {
+        return if lhs.int_rank() >= rhs.int_rank() {
+            lhs
+        } else {
+            rhs
+        };
+    }
+    let (unsigned_ty, signed_ty) = if lhs.is_unsigned() {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    };
+    if unsigned_ty.int_rank() >= signed_ty.int_rank() {
+        unsigned_ty
+    } else if signed_ty.size() > unsigned_ty.size() {
+        signed_ty
+    } else {
+        signed_ty.to_unsigned()
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Bool => write!(f, "_Bool"),
+            Type::Char => write!(f, "char"),
+            Type::UChar => write!(f, "unsigned char"),
+            Type::Short => write!(f, "short"),
+            Type::UShort => write!(f, "unsigned short"),
+            Type::Int => write!(f, "int"),
+            Type::UInt => write!(f, "unsigned int"),
+            Type::Long => write!(f, "long"),
+            Type::ULong => write!(f, "unsigned long"),
+            Type::LongLong => write!(f, "long long"),
+            Type::ULongLong => write!(f, "unsigned long long"),
+            Type::Float => write!(f, "float"),
+            Type::Double => write!(f, "double"),
+            Type::LongDouble => write!(f, "long double"),
+            Type::Ptr(base_ty) => write!(f, "{} *", base_ty),
+            Type::Array(elem_ty, len) => write!(f, "{} [{}]", elem_ty, len),
+            Type::Vla(elem_ty, _) => write!(f, "{} [*]", elem_ty),
+            Type::Func(ret_ty, param_types, _, is_variadic) => {
+                let mut params: Vec<String> = param_types.iter().map(|ty| ty.to_string()).collect();
+                if *is_variadic && !params.is_empty() {
+                    params.push("...".to_string());
+                } else if !*is_variadic && params.is_empty() {
+                    params.push("void".to_string());
+                }
+                write!(f, "{} ({})", ret_ty, params.join(", "))
+            }
+            Type::Struct(st) => {
+                let st = st.borrow();
+                let keyword = if st.is_union { "union" } else { "struct" };
+                match &st.tag {
+                    Some(tag) => write!(f, "{} {}", keyword, tag),
+                    None => write!(f, "{} <anonymous>", keyword),
+                }
+            }
+            // qualifiers of a pointer follow the *
+            Type::Qual(ty, quals) if ty.is_ptr() => write!(f, "{} {}", ty, quals),
+            Type::Qual(ty, quals) => write!(f, "{} {}", quals, ty),
+        }
+    }
 }
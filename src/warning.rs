@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+// Named warnings, which are enabled by default, by -Wall or -Wextra, or only by their
+// own option, and can be turned into errors by -Werror.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    Shadow,                      // a local variable shadows another variable
+    UnusedVariable,              // a local variable is never used
+    UnusedParameter,             // a parameter is never used
+    ImplicitFunctionDeclaration, // a function is called without being declared
+    SignCompare,                 // a signed integer is compared as unsigned
+    ReturnType,                  // a non-void function can return without a value
+    Parentheses,                 // an assignment is used as a condition
+    UnreachableCode,             // a statement can never be executed
+    DiscardedQualifiers,         // a pointer conversion loses qualifiers of the pointed-to type
+    ExcessInitializers,          // an initializer has more elements than the object
+    Varargs,                     // va_arg reads a type changed by the default argument promotions
+}
+
+const WARNINGS: [Warning; 11] = [
+    Warning::Shadow,
+    Warning::UnusedVariable,
+    Warning::UnusedParameter,
+    Warning::ImplicitFunctionDeclaration,
+    Warning::SignCompare,
+    Warning::ReturnType,
+    Warning::Parentheses,
+    Warning::UnreachableCode,
+    Warning::DiscardedQualifiers,
+    Warning::ExcessInitializers,
+    Warning::Varargs,
+];
+
+// which warnings are enabled without any option, and by -Wall and -Wextra
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Group {
+    Default,
+    All,
+    Extra,
+    None,
+}
+
+impl Warning {
+    // the name in -W<name>
+    pub fn name(&self) -> &'static str {
+        match self {
+            Warning::Shadow => "shadow",
+            Warning::UnusedVariable => "unused-variable",
+            Warning::UnusedParameter => "unused-parameter",
+            Warning::ImplicitFunctionDeclaration => "implicit-function-declaration",
+            Warning::SignCompare => "sign-compare",
+            Warning::ReturnType => "return-type",
+            Warning::Parentheses => "parentheses",
+            Warning::UnreachableCode => "unreachable-code",
+            Warning::DiscardedQualifiers => "discarded-qualifiers",
+            Warning::ExcessInitializers => "excess-initializers",
+            Warning::Varargs => "varargs",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Warning> {
+        WARNINGS.iter().copied().find(|warning| warning.name() == name)
+    }
+
+    fn group(&self) -> Group {
+        match self {
+            Warning::ImplicitFunctionDeclaration
+            | Warning::DiscardedQualifiers
+            | Warning::ExcessInitializers
+            | Warning::Varargs => Group::Default,
+            Warning::UnusedVariable | Warning::ReturnType | Warning::Parentheses => Group::All,
+            Warning::UnusedParameter | Warning::SignCompare | Warning::UnreachableCode => Group::Extra,
+            Warning::Shadow => Group::None,
+        }
+    }
+}
+
+// the warnings selected by the -W options; an option naming a warning overrides -Wall and
+// -Wextra regardless of their order, as in GCC
+#[derive(Debug, Clone, Default)]
+pub struct WarningOptions {
+    all: bool,                       // -Wall
+    extra: bool,                     // -Wextra
+    all_errors: bool,                // -Werror
+    enabled: HashMap<Warning, bool>, // -W<name> and -Wno-<name>
+    errors: HashMap<Warning, bool>,  // -Werror=<name> and -Wno-error=<name>
+}
+
+impl WarningOptions {
+    // apply a -W option, returning false if it is not one
+    pub fn parse_option(&mut self, arg: &str) -> bool {
+        let option = match arg.strip_prefix("-W") {
+            Some(option) => option,
+            None => return false,
+        };
+        match option {
+            "all" => self.all = true,
+            "extra" => self.extra = true,
+            "error" => self.all_errors = true,
+            "no-error" => self.all_errors = false,
+            _ => {
+                if let Some(name) = option.strip_prefix("error=") {
+                    let warning = find_warning(name);
+                    self.enabled.insert(warning, true);
+                    self.errors.insert(warning, true);
+                } else if let Some(name) = option.strip_prefix("no-error=") {
+                    self.errors.insert(find_warning(name), false);
+                } else if let Some(name) = option.strip_prefix("no-") {
+                    self.enabled.insert(find_warning(name), false);
+                } else {
+                    self.enabled.insert(find_warning(option), true);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn is_enabled(&self, warning: Warning) -> bool {
+        if let Some(&is_enabled) = self.enabled.get(&warning) {
+            return is_enabled;
+        }
+        match warning.group() {
+            Group::Default => true,
+            Group::All => self.all,
+            Group::Extra => self.extra,
+            Group::None => false,
+        }
+    }
+
+    pub fn is_error(&self, warning: Warning) -> bool {
+        *self.errors.get(&warning).unwrap_or(&self.all_errors)
+    }
+}
+
+fn find_warning(name: &str) -> Warning {
+    match Warning::from_name(name) {
+        Some(warning) => warning,
+        None => panic!("Unknown warning option -W{}", name),
+    }
+}
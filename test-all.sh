@@ -16,7 +16,137 @@ assert() {
     fi
 }
 
+# the warnings reported by compiling with the options, one per line in the order they
+# are reported (the IR is also dumped to stderr)
+assert_warnings() {
+    options="$1"
+    input="$2"
+    expected="$3"
+
+    actual=$(./target/debug/ironcc $options "$input" 2>&1 >/dev/null | grep '^warning: ')
+    if [ "$actual" = "$expected" ]; then
+        echo "Passed $input $options"
+    else
+        echo "Failed at $input $options"
+        echo "Expected warnings:"
+        echo "$expected"
+        echo "Actual warnings:"
+        echo "$actual"
+        exit 1
+    fi
+}
+
+# compiling with the options fails with the message
+assert_error() {
+    options="$1"
+    input="$2"
+    expected="$3"
+
+    output=$(./target/debug/ironcc $options "$input" 2>&1)
+    status="$?"
+    if [ "$status" != 0 ] && echo "$output" | grep -qF -- "$expected"; then
+        echo "Passed $input $options (error)"
+    else
+        echo "Failed at $input $options"
+        echo "A compile error \"$expected\" is expected, but the exit status was $status"
+        exit 1
+    fi
+}
+
 assert 4 ./test/calc.c
 assert 55 ./test/for.c
+assert 97 ./test/while.c
+assert_warnings "-Wall -Wextra" ./test/while.c ""
 assert 30 ./test/if.c
+assert 54 ./test/float.c
+assert 63 ./test/conv.c
+assert 7 ./test/void.c
+assert_error "" ./test/error/void_value.c "void value not ignored as it ought to be"
+assert_error "" ./test/error/void_return.c "Return with a value in function returning void"
+assert 133 ./test/bool.c
+assert 75 ./test/struct.c
+assert 155 ./test/bitfield.c
+assert_error "" ./test/error/bitfield_addr.c "Cannot take address of bit-field"
+assert 178 ./test/enum.c
+assert 118 ./test/typedef.c
+assert 181 ./test/declarator.c
+assert 73 ./test/array.c
+assert 138 ./test/pointer.c
+assert 69 ./test/cast.c
+assert_error "" ./test/error/struct_cast.c "Operand of type struct s where arithmetic or pointer type is required"
+assert 196 ./test/sizeof.c
+assert 131 ./test/global.c
+assert 121 ./test/static.c
+assert_error "" ./test/error/static_after_nonstatic.c "Static declaration of n follows non-static declaration"
+assert_error "" ./test/error/nonstatic_after_static.c "Non-static declaration of n follows static declaration"
+assert_error "" ./test/error/register_addr.c "Address of register variable r requested"
+assert 109 ./test/qual.c
+assert_error "" ./test/error/const_assign.c "Cannot assign to an lvalue of const-qualified type const int"
+assert_error "" ./test/error/const_ptr_assign.c "Cannot assign to an lvalue of const-qualified type const int"
+assert_error "-Werror=discarded-qualifiers" ./test/error/discarded_qual.c "Conversion from const int * to int * discards qualifiers [-Werror=discarded-qualifiers]"
+assert 97 ./test/init.c
+assert 228 ./test/scope.c
+assert_error "" ./test/error/redefinition.c "Redefinition of y"
+assert 250 ./test/variadic.c
+assert 95 ./test/proto.c
+assert_error "" ./test/error/arg_count.c "Wrong number of arguments to add"
+assert_error "" ./test/error/arg_type.c "Cannot convert struct s to int *"
+assert 103 ./test/vla.c
+assert 67 ./test/generic.c
+assert_error "" ./test/error/static_assert.c "Static assertion failed: \"int is 8 bytes\""
+assert_error "" ./test/error/generic_no_match.c "_Generic has no association compatible with double"
+assert 149 ./test/const.c
+assert_error "" ./test/error/const_overflow.c "Integer overflow in constant expression of type int"
+assert_error "" ./test/error/div_zero.c "Invalid size of array: Division by zero in constant expression"
+assert 95 ./test/warn.c
+assert_warnings "" ./test/warn.c "warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]
+warning: Implicit declaration of function abs [-Wimplicit-function-declaration]"
+assert_warnings "-Wall" ./test/warn.c "warning: Unused variable unused_local [-Wunused-variable]
+warning: Control reaches end of non-void function no_return [-Wreturn-type]
+warning: Assignment used as a condition [-Wparentheses]
+warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]
+warning: Implicit declaration of function abs [-Wimplicit-function-declaration]"
+assert_warnings "-Wextra" ./test/warn.c "warning: Unused parameter unused [-Wunused-parameter]
+warning: Comparison of integers of different signs: int and unsigned int [-Wsign-compare]
+warning: Code after return is unreachable [-Wunreachable-code]
+warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]
+warning: Implicit declaration of function abs [-Wimplicit-function-declaration]"
+assert_warnings "-Wshadow" ./test/warn.c "warning: Declaration of k shadows a previous local declaration [-Wshadow]
+warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]
+warning: Implicit declaration of function abs [-Wimplicit-function-declaration]"
+assert_warnings "-Wall -Wextra -Wshadow" ./test/warn.c "warning: Unused parameter unused [-Wunused-parameter]
+warning: Unused variable unused_local [-Wunused-variable]
+warning: Comparison of integers of different signs: int and unsigned int [-Wsign-compare]
+warning: Control reaches end of non-void function no_return [-Wreturn-type]
+warning: Assignment used as a condition [-Wparentheses]
+warning: Code after return is unreachable [-Wunreachable-code]
+warning: Declaration of k shadows a previous local declaration [-Wshadow]
+warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]
+warning: Implicit declaration of function abs [-Wimplicit-function-declaration]"
+assert_warnings "-Wall -Wno-unused-variable -Wno-implicit-function-declaration" ./test/warn.c "warning: Control reaches end of non-void function no_return [-Wreturn-type]
+warning: Assignment used as a condition [-Wparentheses]
+warning: Conversion from const int * to int * discards qualifiers [-Wdiscarded-qualifiers]
+warning: Excess elements in initializer of int [2] [-Wexcess-initializers]
+warning: short is promoted to int when passed through ... [-Wvarargs]"
+assert_warnings "-Wsign-compare -Wno-discarded-qualifiers -Wno-excess-initializers -Wno-varargs -Wno-implicit-function-declaration" ./test/warn.c "warning: Comparison of integers of different signs: int and unsigned int [-Wsign-compare]"
+assert_error "-Werror" ./test/warn.c "Conversion from const int * to int * discards qualifiers [-Werror=discarded-qualifiers]"
+assert_error "-Werror=sign-compare" ./test/warn.c "Comparison of integers of different signs: int and unsigned int [-Werror=sign-compare]"
+assert_error "-Wall -Werror=unused-variable -Wno-error=unused-variable -Werror=return-type" ./test/warn.c "Control reaches end of non-void function no_return [-Werror=return-type]"
+assert_error "-Wbogus" ./test/warn.c "Unknown warning option -Wbogus"
+assert 49 ./test/flow.c
+assert_warnings "-Wall" ./test/flow.c "warning: Control reaches end of non-void function never [-Wreturn-type]"
+assert_warnings "-Wextra" ./test/flow.c "warning: Code after return is unreachable [-Wunreachable-code]
+warning: Code after a block which never completes is unreachable [-Wunreachable-code]"
+assert_warnings "-Wall -Wextra -Wno-return-type -Wno-unreachable-code" ./test/flow.c ""
+assert_error "-Wall -Werror=return-type" ./test/flow.c "Control reaches end of non-void function never [-Werror=return-type]"
 echo OK
\ No newline at end of file
@@ -0,0 +1,43 @@
+struct vec {
+    int len;
+    long items[4];
+};
+
+int sum(int *p, int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        s = s + p[i];
+    return s;
+}
+
+int sum_rows(int rows[][3], int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        s = s + rows[i][0] + rows[i][1] + rows[i][2];
+    return s;
+}
+
+int main() {
+    int a[3][3];
+    int i;
+    int j;
+    for (i = 0; i < 3; i = i + 1)
+        for (j = 0; j < 3; j = j + 1)
+            a[i][j] = i * 3 + j;
+    int ret = a[2][1] + sum(a[1], 3) + sum_rows(a, 2);
+    int (*rows)[3] = a;
+    ret = ret + rows[2][2] + 1[rows][0];
+    char s[2][3][4];
+    s[1][2][3] = 7;
+    char *c = s[1][2];
+    ret = ret + c[3] + sizeof(char[3][4]);
+    struct vec v;
+    v.items[2] = 5;
+    struct vec *pv = &v;
+    ret = ret + pv->items[2];
+    int (*whole)[3][3] = &a;
+    ret = ret + (*whole)[1][1];
+    return ret;
+}
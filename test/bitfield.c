@@ -0,0 +1,54 @@
+struct flags {
+    unsigned ready : 1;
+    unsigned mode : 3;
+    int delta : 4;
+    unsigned : 0;
+    unsigned short code : 9;
+    _Bool on : 1;
+    long big : 40;
+    int tail : 30;
+};
+
+union reg {
+    unsigned word;
+    struct {
+        unsigned low : 8;
+        unsigned mid : 16;
+        unsigned high : 8;
+    };
+};
+
+struct flags set(struct flags f) {
+    f.mode = f.mode + 1;
+    return f;
+}
+
+int main() {
+    struct flags f;
+    f.ready = 1;
+    f.mode = 13;
+    f.delta = -3;
+    f.code = 511;
+    f.on = 2;
+    f.big = -1;
+    f.tail = 100;
+    int ret = f.ready + f.mode + f.on;
+    if (f.delta < 0)
+        ret = ret + 10;
+    if (f.mode - 6 < 0)
+        ret = ret + 10;
+    if (f.code == 511)
+        ret = ret + 10;
+    if (f.big == -1)
+        ret = ret + 10;
+    f = set(f);
+    ret = ret + f.mode + f.tail;
+    union reg r;
+    r.word = 0;
+    r.mid = 65535;
+    r.high = 257;
+    if (r.word == 33554176)
+        ret = ret + 1;
+    ret = ret + (f.ready = 3);
+    return ret;
+}
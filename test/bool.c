@@ -0,0 +1,26 @@
+#include <stdbool.h>
+
+bool is_positive(int n) {
+    return n > 0;
+}
+
+int count(_Bool a, _Bool b) {
+    return a + b;
+}
+
+int main() {
+    _Bool b = 256;
+    bool t = true;
+    bool f = false;
+    double d = 0.5;
+    _Bool bd = d;
+    int *p = 0;
+    _Bool bp = p;
+    int r = b + t + f + bd + bp;
+    r = r + count(2, 0) * 10;
+    if (is_positive(5))
+        r = r + 100;
+    bool c = b + t;
+    r = r + c * 20;
+    return r;
+}
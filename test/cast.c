@@ -0,0 +1,40 @@
+struct s {
+    int x;
+};
+
+int touch(int n) {
+    return n + 1;
+}
+
+int main() {
+    int ret = 0;
+    int big = 1000;
+    char c = (char)big;
+    unsigned char uc = (unsigned char)-1;
+    ret = ret + (c == -24) + (uc == 255);
+    short sh = (short)70000;
+    ret = ret + (sh == 4464);
+    long l = (long)-1;
+    unsigned long ul = (unsigned long)(unsigned)-1;
+    ret = ret + (l < 0) + (ul == 4294967295);
+    ret = ret + (int)3.9 + (int)-3.9;
+    double d = (double)7 / 2;
+    ret = ret + (int)(d * 2);
+    float f = (float)(unsigned char)300;
+    ret = ret + (int)f;
+    int x = 5;
+    long addr = (long)&x;
+    int *p = (int *)addr;
+    ret = ret + *p;
+    char *cp = (char *)p;
+    ret = ret + *(int *)cp;
+    signed char m = -1;
+    void *vp = (void *)m;
+    ret = ret + ((long)vp == -1);
+    ret = ret + (_Bool)0.5 + (_Bool)p;
+    (void)touch(1);
+    (void)x;
+    struct s st;
+    (void)st;
+    return ret;
+}
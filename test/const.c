@@ -0,0 +1,46 @@
+enum big { SMALL = -1, HUGE = 4294967295u };
+enum wide { WIDE = (long)1 * 4294967296 };
+
+int arr[(unsigned char)300];
+char name[sizeof(long) * 2 - 1];
+int trunc_size[(int)2.9 + 1];
+int g = 2147483647 / 2 + 1;
+unsigned umax = 0u - 1;
+short wrapped = (short)70000;
+long shifted = (long)(unsigned)-1 * 2;
+double third = 1 / 3.0 * 3;
+float ratio = (float)0.1 * 10;
+int table[] = {[1 + 1] = 5, [sizeof(int) ... sizeof(int) + 1] = 7};
+int *after = &arr[2] + 1;
+long addr = (long)&g;
+long gap = &arr[10] - &arr[3];
+int copies[sizeof table / sizeof table[0]];
+_Bool flag = 0.5;
+
+struct flags {
+    unsigned a : sizeof(char) * 3;
+    unsigned b : 5;
+};
+
+_Alignas(sizeof(long) * 2) char aligned[3];
+
+_Static_assert((unsigned char)-1 == 255, "unsigned char wraps");
+_Static_assert((-1 < 0u) == 0, "-1 is converted to unsigned");
+_Static_assert((long)4294967296 * 2 == 8589934592, "long arithmetic");
+_Static_assert((signed char)200 == -56, "signed char truncates");
+_Static_assert(sizeof(enum big) == 8, "enum with -1 and UINT_MAX is long");
+_Static_assert(sizeof copies == sizeof table, "sizeof of a variable is constant");
+
+int main() {
+    int r = 0;
+    static int *p = &g;
+    static int counts[sizeof r] = {3};
+    r = r + sizeof(arr) / sizeof(int);
+    r = r + sizeof(name) + sizeof(trunc_size);
+    r = r + (g == 1073741824) + (umax == 4294967295u) + (wrapped == 4464) + (shifted == 8589934590);
+    r = r + (third == 1.0) + (ratio == (float)1) + table[2] + table[4] + table[5];
+    r = r + (after - arr) + ((int *)addr == p) + gap + flag;
+    r = r + sizeof(struct flags) + sizeof(aligned) + sizeof(enum wide) + (HUGE == 4294967295u);
+    r = r + sizeof(copies) / sizeof(int) + counts[0] + sizeof counts;
+    return r;
+}
@@ -0,0 +1,29 @@
+int sum(char a, short b, long c) {
+    return a + b + c;
+}
+
+int main() {
+    char c = 200;
+    unsigned char uc = 200;
+    unsigned int u = 1;
+    int neg = -1;
+    int r = 0;
+    if (c < 0)
+        r = r + 1;
+    if (uc + uc == 400)
+        r = r + 2;
+    if (neg < u)
+        r = r + 100;
+    if (neg > u)
+        r = r + 4;
+    long big = 3000000000;
+    if (big / 1000 == 3000000)
+        r = r + 8;
+    int cmp = (1 < 2) + (2 <= 2);
+    r = r + cmp * 16;
+    r = r + sum(1, 2, 3);
+    unsigned int half = 4294967295u / 2;
+    if (half == 2147483647)
+        r = r + 10;
+    return r;
+}
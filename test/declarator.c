@@ -0,0 +1,54 @@
+typedef int (*binop)(int, int);
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int sub(int a, int b) {
+    return a - b;
+}
+
+int second(int, int b) {
+    return b;
+}
+
+int apply(int (*op)(int, int), int a, int b) {
+    return op(a, b);
+}
+
+int (*choose(int i))(int, int) {
+    if (i)
+        return sub;
+    return add;
+}
+
+struct ops {
+    binop fn;
+    int (*unary)(int (int, int));
+};
+
+int call_add(int f(int, int)) {
+    return f(3, 4);
+}
+
+int main() {
+    int (*fp)(int, int) = add;
+    int ret = fp(1, 2);
+    ret = ret + (*fp)(3, 4);
+    fp = &sub;
+    ret = ret + apply(fp, 10, 5);
+    ret = ret + choose(0)(6, 7) + choose(1)(9, 1);
+    struct ops ops;
+    ops.fn = second;
+    ops.unary = call_add;
+    ret = ret + ops.fn(100, 2) + ops.unary(add);
+    binop *pp = &fp;
+    ret = ret + (**pp)(20, 10);
+    ret = ret + sizeof(int (*)[4]) + sizeof(int[3][4]) + sizeof(char *[2]) + sizeof(int (*)(void));
+    int x = 300;
+    ret = ret + (char)x + (long)2;
+    int *p = &x;
+    char *cp = (char *)p;
+    ret = ret + (int)(long)(int *)cp - (int)(long)p;
+    return ret;
+}
@@ -0,0 +1,43 @@
+enum color {
+    RED,
+    GREEN = 5,
+    BLUE,
+};
+
+enum error {
+    ERR_IO = -2,
+    ERR_NOMEM,
+    OK
+};
+
+enum big {
+    SMALL = 1,
+    HUGE = 4294967296
+};
+
+enum color next(enum color c) {
+    if (c == RED)
+        return GREEN;
+    return BLUE;
+}
+
+int main() {
+    enum color c = next(RED);
+    int ret = c + BLUE;
+    enum error e = ERR_NOMEM;
+    if (e < 0)
+        ret = ret + 10;
+    enum color u = RED;
+    if (u - 1 > 0)
+        ret = ret + 20;
+    if (HUGE / 2 == 2147483648)
+        ret = ret + 30;
+    enum { A = 3, B = A * 2, C } x = C;
+    ret = ret + x;
+    {
+        int RED = 100;
+        ret = ret + RED;
+    }
+    ret = ret + RED + OK;
+    return ret;
+}
@@ -0,0 +1,12 @@
+struct s {
+    int a;
+};
+
+int f(int *p) {
+    return *p;
+}
+
+int main() {
+    struct s v;
+    return f(v);
+}
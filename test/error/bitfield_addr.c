@@ -0,0 +1,9 @@
+struct s {
+    int a : 3;
+};
+
+int main() {
+    struct s v;
+    int *p = &v.a;
+    return *p;
+}
@@ -0,0 +1,5 @@
+int main() {
+    const int c = 1;
+    c = 2;
+    return c;
+}
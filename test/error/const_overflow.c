@@ -0,0 +1,5 @@
+enum { BIG = 2147483647 + 1 };
+
+int main() {
+    return BIG;
+}
@@ -0,0 +1,5 @@
+int main() {
+    const int c = 1;
+    int *p = &c;
+    return *p;
+}
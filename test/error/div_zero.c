@@ -0,0 +1,5 @@
+int a[4 / (2 - 2)];
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,4 @@
+int main() {
+    double d = 1;
+    return _Generic(d, int: 1, long: 2);
+}
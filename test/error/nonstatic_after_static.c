@@ -0,0 +1,6 @@
+static int n;
+int n;
+
+int main() {
+    return n;
+}
@@ -0,0 +1,9 @@
+int main() {
+    int x = 1;
+    {
+        int y = 2;
+        int y = 3;
+        x = y;
+    }
+    return x;
+}
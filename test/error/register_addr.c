@@ -0,0 +1,5 @@
+int main() {
+    register int r = 1;
+    int *p = &r;
+    return *p;
+}
@@ -0,0 +1,6 @@
+int n;
+static int n;
+
+int main() {
+    return n;
+}
@@ -0,0 +1,5 @@
+_Static_assert(sizeof(int) == 8, "int is 8 bytes");
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,9 @@
+struct s {
+    int a;
+};
+
+int main() {
+    struct s v;
+    v.a = 1;
+    return (int)v;
+}
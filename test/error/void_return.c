@@ -0,0 +1,8 @@
+void f(int n) {
+    return n;
+}
+
+int main() {
+    f(1);
+    return 0;
+}
@@ -0,0 +1,7 @@
+void f() {
+}
+
+int main() {
+    int x = f();
+    return x;
+}
@@ -0,0 +1,27 @@
+double half(double x) {
+    return x / 2;
+}
+
+float add(float a, float b) {
+    return a + b;
+}
+
+long double scale(long double x, int n) {
+    return x * n;
+}
+
+int main() {
+    double d = 3.5;
+    float f = 1.5f;
+    long double ld = 2.0L;
+    d = half(d * 4) + add(f, .5f);
+    ld = scale(ld, 3) * d;
+    if (d != 9.0) {
+        return 1;
+    }
+    if (-1e1 < d / 2) {
+        d = ld;
+    }
+    int n = d;
+    return n;
+}
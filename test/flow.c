@@ -0,0 +1,49 @@
+int sign(int n) {
+    if (n < 0)
+        return -1;
+    else if (n == 0)
+        return 0;
+    else
+        return 1;
+}
+
+int first_over(int *a, int limit) {
+    for (int i = 0;; i = i + 1) {
+        if (limit < a[i])
+            return i;
+    }
+}
+
+int constant(int n) {
+    if (1)
+        return n + 4;
+}
+
+int never(int n) {
+    if (0)
+        return n;
+    n = n + 1;
+}
+
+int after(int n) {
+    {
+        return n;
+        n = n + 100;
+    }
+    n = n + 200;
+}
+
+int g;
+
+void set(int n) {
+    if (n)
+        return;
+    g = 7;
+}
+
+int main() {
+    int a[5] = {3, 9, 1, 20, 5};
+    set(0);
+    set(1);
+    return sign(-5) + sign(0) + sign(8) + first_over(a, 10) * 10 + constant(2) + after(6) + g;
+}
@@ -0,0 +1,41 @@
+_Static_assert(sizeof(long) == 8, "long is 64-bit");
+_Static_assert(sizeof(int) * 2 == sizeof(long), "int is half of long");
+
+struct point {
+    int x;
+    _Static_assert(sizeof(int) == 4, "int is 32-bit");
+    int y;
+};
+
+_Static_assert(sizeof(struct point) == 8, "no padding in point");
+
+int kind_int() { return 1; }
+int kind_double() { return 2; }
+
+int classify(double d, int i) {
+    return _Generic(d, int: 10, double: 20, default: 30) + _Generic(i, int: 1, double: 2);
+}
+
+int main() {
+    _Static_assert(1, "block scope");
+    int r = 0;
+    const int ci = 3;
+    int arr[4];
+    char *s = "ab";
+    long l = 5;
+    unsigned u = 1;
+    float f = 1.5;
+    r = r + _Generic(ci, int: 1, const int: 100, default: 50);
+    r = r + _Generic(arr, int *: 2, int [4]: 200, default: 60);
+    r = r + _Generic(s, char *: 3, const char *: 300);
+    r = r + _Generic(l, long: 4, int: 400, long long: 40);
+    r = r + _Generic(u, int: 500, default: 5);
+    r = r + _Generic(f, float: 6, double: 600);
+    r = r + _Generic(1.0, float: 700, double: 7);
+    r = r + _Generic(main, int (*)(): 8, default: 80);
+    r = r + _Generic(r = 100, int: 9);
+    r = r + _Generic(&ci, const int *: kind_int, int *: kind_double)();
+    r = r + classify(1.0, 2);
+    _Static_assert(_Generic(1L, long: 8, default: 0) == sizeof(long), "generic in a constant expression");
+    return r;
+}
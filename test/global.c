@@ -0,0 +1,58 @@
+struct point {
+    int x;
+    int y;
+};
+
+int twice(int x);
+
+int counter;
+int counter;
+extern int limit;
+int limit = 10;
+long big = 3000000000 / 3;
+char *msg = "hello, " "world";
+char *tail = "hello" + 3;
+int arr[5];
+int arr[];
+int *p = &arr[2];
+int *q = arr + 4;
+struct point pt;
+int *py = &pt.y;
+double d = 1.5 * 2;
+int (*fp)(int) = twice;
+_Bool flag = 7;
+long gap = &arr[4] - &arr[1];
+char esc[sizeof "a\tb\n\x41\101\0"];
+int unsized[];
+
+int twice(int x) {
+    return x * 2;
+}
+
+int bump() {
+    counter = counter + 1;
+    return counter;
+}
+
+int main() {
+    extern int limit;
+    int ret = 0;
+    bump();
+    bump();
+    ret = ret + counter;
+    ret = ret + limit;
+    ret = ret + big / 100000000;
+    ret = ret + (msg[7] == 119) + (tail[0] == 108) + (tail[2] == 0);
+    *p = 5;
+    q[0] = 6;
+    ret = ret + arr[2] + arr[4];
+    *py = 7;
+    ret = ret + pt.y;
+    ret = ret + d;
+    ret = ret + fp(4);
+    ret = ret + flag;
+    ret = ret + gap;
+    ret = ret + sizeof(esc) + unsized[0];
+    ret = ret + "\x41\101"[1];
+    return ret;
+}
@@ -0,0 +1,102 @@
+struct point {
+    int x;
+    int y;
+};
+
+struct flags {
+    unsigned a : 3;
+    int b : 5;
+    char c;
+};
+
+union value {
+    int i;
+    char bytes[8];
+};
+
+struct line {
+    struct point from;
+    struct point to;
+    char name[8];
+};
+
+struct anon {
+    int tag;
+    union {
+        int n;
+        double d;
+    };
+};
+
+int g_arr[] = {1, 2, 3, 4};
+int g_zero[10] = {[9] = 9};
+int g_range[10] = {[2 ... 5] = 7, 8};
+char g_str[] = "hello";
+char g_short[8] = "abc";
+struct point g_pt = {.y = 2, .x = 1};
+struct flags g_flags = {5, -3, 120};
+union value g_val = {.bytes = {1, 1}};
+struct line g_line = {{1, 2}, 3, 4, "line"};
+int g_mat[2][3] = {1, 2, 3, 4, 5, 6};
+int *g_ptr = &g_arr[2];
+char *g_names[] = {"ab", "cd", 0};
+int *g_lit = (int[]){10, 20, 30};
+struct anon g_anon = {1, .n = 5};
+double g_d[3] = {1.5, [2] = 2.5};
+
+int sum(int *a, int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        s = s + a[i];
+    return s;
+}
+
+int check_globals() {
+    int ret = 0;
+    ret = ret + sum(g_arr, sizeof(g_arr) / sizeof(int));
+    ret = ret + g_zero[9] + g_zero[0];
+    ret = ret + sum(g_range, 10);
+    ret = ret + sizeof(g_str) + g_str[4] - 100;
+    ret = ret + g_short[2] - 90 + g_short[7];
+    ret = ret + g_pt.x * 10 + g_pt.y;
+    ret = ret + g_flags.a + g_flags.b + g_flags.c - 100;
+    ret = ret + g_val.i;
+    ret = ret + g_line.from.x + g_line.from.y + g_line.to.x + g_line.to.y + g_line.name[3] - 90;
+    ret = ret + g_mat[1][2] + *g_ptr + g_names[1][1] - 90 + (g_names[2] == 0);
+    ret = ret + g_lit[2] / 10 + g_anon.tag + g_anon.n + g_d[0] * 2 + g_d[1] + g_d[2] * 2;
+    return ret;
+}
+
+int check_locals(int k) {
+    int ret = 0;
+    int arr[] = {k, k + 1, k + 2};
+    int big[20] = {[0] = 1, [19] = k};
+    int consts[10] = {1, 2, 3, 4, 5, 6, 7, 8, 9, 10};
+    char str[] = "local";
+    char buf[16] = "xy";
+    struct point pt = {.y = k};
+    struct flags fl = {k, -k};
+    struct line ln = {.to = {k, k}, .name = "ab"};
+    struct point pts[3] = {[1].y = 5, {6, 7}, [0].x = 8};
+    struct point copy[2] = {pt, pt};
+    int scalar = {k * 2};
+    int *lit = (int[]){k, k * 2};
+    int n = (int){k} + ((struct point){1, 2}).y;
+    union value val = {k};
+
+    ret = ret + sizeof(arr) + arr[2];
+    ret = ret + big[0] + big[19] + big[10];
+    ret = ret + sum(consts, 10);
+    ret = ret + sizeof(str) + str[4] - 100 + buf[1] - 110 + buf[15];
+    ret = ret + pt.x + pt.y;
+    ret = ret + fl.a + fl.b + fl.c;
+    ret = ret + ln.from.x + ln.to.x + ln.to.y + ln.name[1] - 90 + ln.name[2];
+    ret = ret + pts[0].x + pts[1].y + pts[2].x + pts[2].y;
+    ret = ret + copy[1].y + scalar + lit[1] + n + val.i + sizeof((int[]){1, 2, 3});
+    return ret;
+}
+
+int main() {
+    return check_globals() + check_locals(3);
+}
@@ -0,0 +1,36 @@
+struct pair {
+    int a;
+    long b;
+};
+
+long count(int *begin, int *end) {
+    return end - begin;
+}
+
+int main() {
+    int a[5];
+    int i;
+    for (i = 0; i < 5; i = i + 1)
+        *(a + i) = i * 10;
+    int *p = a;
+    int ret = *(p + 1) + *(2 + p) + *(a + 4 - 1);
+    int *q = &a[4];
+    ret = ret + (q - p) + count(a, a + 5);
+    ret = ret + (q > p) + (p >= q) * 100 + (p < q) + (p <= p) + (p == a) + (q != 0);
+    int *null = 0;
+    if (null == 0)
+        ret = ret + 1;
+    if (0 != p)
+        ret = ret + 1;
+    struct pair pairs[3];
+    struct pair *pp = pairs + 2;
+    ret = ret + (pp - pairs) + (pp - 1 - pairs);
+    void *v = p;
+    if (v == p)
+        ret = ret + 1;
+    char *c = (char *)(a + 2);
+    ret = ret + (c - (char *)a);
+    long idx = -1;
+    ret = ret + *(q + idx) + q[-2];
+    return ret;
+}
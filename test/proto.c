@@ -0,0 +1,62 @@
+int add(int a, int b);
+int add(int, int);
+int twice();
+long sum3(long, long, long);
+double half(double x);
+static int hidden(void);
+int (*get_op())(int, int);
+
+int apply(int (*op)(), int a, int b) {
+    return op(a, b);
+}
+
+int main() {
+    int r = 0;
+    int scale(int);
+    r = r + add(1, 2);
+    r = r + twice(4);
+    r = r + sum3(1, 2, 3);
+    r = r + half(10);
+    r = r + hidden();
+    r = r + scale(3);
+    r = r + get_op()(5, 6);
+    r = r + apply(add, 7, 8);
+    r = r + old_style(2, 3, 4.5);
+    r = r + abs(0 - 9);
+    return r;
+}
+
+int add(int a, int b) {
+    return a + b;
+}
+
+int twice(int n) {
+    return n * 2;
+}
+
+long sum3(long a, long b, long c) {
+    return a + b + c;
+}
+
+double half(double x) {
+    return x / 2;
+}
+
+static int hidden() {
+    return 20;
+}
+
+int scale(int n) {
+    return n * 10;
+}
+
+int (*get_op())(int, int) {
+    return add;
+}
+
+int old_style(a, c, f)
+    char c;
+    float f;
+{
+    return a * 100 + c * 10 + f * 2 + sizeof(c) + sizeof(f);
+}
@@ -0,0 +1,63 @@
+struct point {
+    int x;
+    int y;
+};
+
+typedef const int cint;
+typedef int arr3[3];
+
+const int limit = 10;
+const char *greeting = "hi";
+volatile int counter;
+_Atomic int hits;
+_Atomic(long) total;
+
+int sum(const int *restrict a, int n) {
+    int s = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        s = s + a[i];
+    return s;
+}
+
+void copy(int *restrict dst, const int *restrict src) {
+    *dst = *src;
+}
+
+int first(const struct point *p) {
+    return p->x;
+}
+
+int main() {
+    const arr3 nums;
+    int buf[3];
+    int *const p = buf;
+    const int *cp;
+    int const volatile cv = 4;
+    cint c = 5;
+    struct point pt;
+    const struct point *ppt = &pt;
+    int ret = 0;
+
+    buf[0] = 1;
+    buf[1] = 2;
+    buf[2] = 3;
+    *p = 7;
+    cp = buf;
+    ret = ret + *cp;
+    ret = ret + sum(buf, 3);
+    copy(&ret, cp);
+    ret = ret + limit + c + cv;
+    counter = 3;
+    counter = counter + 1;
+    ret = ret + counter;
+    hits = 2;
+    total = hits + 40;
+    ret = ret + total;
+    pt.x = 6;
+    pt.y = 8;
+    ret = ret + first(ppt) + ppt->y;
+    ret = ret + greeting[1] - 100 + sizeof(nums);
+    ret = ret + (cp == buf) + sizeof(cv) + sizeof(const char);
+    return ret;
+}
@@ -0,0 +1,77 @@
+int x = 5;
+int y = 7;
+
+int global_x() {
+    return x;
+}
+
+int shadow_param(int a) {
+    int r = a;
+    {
+        int a = 100;
+        r = r + a;
+    }
+    return r + a;
+}
+
+int loop_sum() {
+    int s = 0;
+    for (int i = 0; i < 4; i = i + 1) {
+        int i2 = i * 2;
+        s = s + i2;
+    }
+    for (int i = 10; i < 12; i = i + 1)
+        s = s + i;
+    return s;
+}
+
+int counter() {
+    int n = 0;
+    {
+        static int c = 0;
+        c = c + 1;
+        n = n + c;
+    }
+    {
+        static int c = 10;
+        c = c + 1;
+        n = n + c;
+    }
+    return n;
+}
+
+int main() {
+    int r = x;
+    int x = 1;
+    r = r + x;
+    {
+        int x = 2;
+        r = r + x;
+        {
+            int x = 3;
+            r = r + x;
+        }
+        r = r + x;
+    }
+    r = r + x;
+    {
+        int z = 20;
+        r = r + z;
+    }
+    {
+        int z = 30;
+        r = r + z;
+    }
+    {
+        extern int x;
+        r = r + x;
+    }
+    typedef int T;
+    {
+        T T = 4;
+        r = r + T;
+    }
+    T t = 1;
+    counter();
+    return r + t + global_x() + shadow_param(1) + loop_sum() + counter();
+}
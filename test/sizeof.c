@@ -0,0 +1,43 @@
+struct s {
+    char c;
+    _Alignas(16) int x;
+    long double ld;
+};
+
+struct t {
+    char c;
+    _Alignas(double) char d;
+};
+
+int g(int x) {
+    return x;
+}
+
+int main() {
+    int a[4];
+    char buf[sizeof(a) / sizeof a[0] * 2];
+    long l;
+    _Alignas(32) char aligned[3];
+    _Alignas(0) int z;
+    struct s v;
+    int *p = a;
+    int ret = 0;
+
+    ret = ret + sizeof buf;
+    ret = ret + sizeof(a);
+    ret = ret + sizeof p;
+    ret = ret + sizeof *p;
+    ret = ret + sizeof(struct s);
+    ret = ret + _Alignof(struct s);
+    ret = ret + sizeof(struct t);
+    ret = ret + _Alignof(long double);
+    ret = ret + _Alignof(char);
+    ret = ret + sizeof(l + 1);
+    ret = ret + sizeof(char) + sizeof(short);
+    ret = ret + sizeof v.ld;
+    ret = ret + sizeof(g(1));
+    ret = ret + sizeof(int[_Alignof(double)]);
+    z = (long)aligned - (long)aligned / 32 * 32;
+    ret = ret + z;
+    return ret;
+}
@@ -0,0 +1,42 @@
+static int hidden = 5;
+static int helper(int x);
+extern int hidden;
+_Thread_local int tls = 3;
+static _Thread_local int tls2;
+
+int helper(int x) {
+    return x + hidden;
+}
+
+int count() {
+    static int n;
+    static int *self = &n;
+    n = n + 1;
+    return *self;
+}
+
+int other() {
+    static int n = 100;
+    n = n + 1;
+    return n;
+}
+
+int main() {
+    auto int a = 1;
+    register int r = 2;
+    extern _Thread_local int tls;
+    int ret = 0;
+    int i;
+    for (i = 0; i < 3; i = i + 1)
+        count();
+    ret = ret + count();
+    other();
+    ret = ret + other();
+    ret = ret + helper(1);
+    tls = tls + 1;
+    ret = ret + tls;
+    tls2 = 2;
+    ret = ret + tls2;
+    ret = ret + a + r;
+    return ret;
+}
@@ -0,0 +1,135 @@
+struct list;
+
+struct point {
+    int x;
+    int y;
+};
+
+struct rect {
+    struct point min;
+    struct point max;
+};
+
+struct list {
+    int val;
+    struct list *next;
+};
+
+union number {
+    int i;
+    char c;
+    double d;
+};
+
+struct big {
+    long a;
+    long b;
+    long c;
+};
+
+struct mixed {
+    char c;
+    double d;
+};
+
+struct extended {
+    long double x;
+};
+
+int area(struct rect r) {
+    return (r.max.x - r.min.x) * (r.max.y - r.min.y);
+}
+
+struct point make_point(int x, int y) {
+    struct point p;
+    p.x = x;
+    p.y = y;
+    return p;
+}
+
+struct big make_big(long n) {
+    struct big b;
+    b.a = n;
+    b.b = n * 2;
+    b.c = n * 3;
+    return b;
+}
+
+long sum_big(struct big b) {
+    return b.a + b.b + b.c;
+}
+
+struct mixed half(struct mixed m) {
+    m.d = m.d / 2;
+    return m;
+}
+
+struct extended scale(struct extended e, long double k) {
+    e.x = e.x * k;
+    return e;
+}
+
+int sum_list(struct list *l) {
+    int sum = 0;
+    for (; l; l = l->next)
+        sum = sum + l->val;
+    return sum;
+}
+
+int main() {
+    struct rect r;
+    r.min = make_point(1, 2);
+    r.max.x = 4;
+    r.max.y = 6;
+    int ret = area(r);
+
+    struct list c;
+    struct list b;
+    struct list a;
+    a.val = 1;
+    a.next = &b;
+    b.val = 2;
+    b.next = &c;
+    c.val = 3;
+    c.next = 0;
+    ret = ret + sum_list(&a);
+
+    union number n;
+    n.i = 65;
+    ret = ret + n.c - 60;
+
+    struct {
+        int tag;
+        union {
+            int i;
+            double d;
+        };
+    } anon;
+    anon.tag = 1;
+    anon.i = 7;
+    ret = ret + anon.tag + anon.i;
+
+    ret = ret + sum_big(make_big(2));
+    ret = ret + make_big(1).c;
+
+    struct mixed m;
+    m.c = 3;
+    m.d = 9.0;
+    struct mixed h = half(m);
+    ret = ret + h.c + h.d;
+
+    struct point p = make_point(7, 8);
+    struct point *pp = &p;
+    struct point q;
+    q = *pp;
+    pp->x = 0;
+    ret = ret + q.x + p.x;
+
+    struct extended e;
+    e.x = 2.5;
+    e = scale(e, 4);
+    long double (*scale_ld)(struct extended, long double) = (long double (*)(struct extended, long double))scale;
+    ret = ret + e.x + scale_ld(e, 0.5);
+
+    return ret;
+}
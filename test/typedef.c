@@ -0,0 +1,43 @@
+typedef unsigned long size_t;
+typedef int myint, *intptr;
+typedef struct node {
+    int val;
+    struct node *next;
+} Node;
+typedef enum { OFF, ON } Switch;
+
+int get(intptr p) {
+    return *p;
+}
+
+int twice(myint myint) {
+    return myint * 2;
+}
+
+int main() {
+    size_t n = 3;
+    myint a = 4;
+    intptr p = &a;
+    Node node;
+    node.val = get(p);
+    node.next = 0;
+    Switch s = ON;
+    int ret = n + node.val + s;
+    int b = 5;
+    a * b;
+    {
+        typedef long T;
+        T x = 6;
+        ret = ret + x;
+        {
+            int T = 10;
+            ret = ret + T * b;
+        }
+    }
+    {
+        int size_t = 20;
+        ret = ret + size_t;
+    }
+    ret = ret + twice(7) + a * b;
+    return ret;
+}
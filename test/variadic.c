@@ -0,0 +1,100 @@
+#include <stdarg.h>
+
+int sprintf(char *buf, const char *fmt, ...);
+int strcmp(const char *s1, const char *s2);
+
+struct pair {
+    long a;
+    double b;
+};
+
+struct big {
+    long x;
+    long y;
+    long z;
+};
+
+int sum_ints(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    int s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + va_arg(ap, int);
+    va_end(ap);
+    return s;
+}
+
+double sum_doubles(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    double s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + va_arg(ap, double);
+    va_end(ap);
+    return s;
+}
+
+long mixed(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    long l = va_arg(ap, long);
+    double d = va_arg(ap, double);
+    struct pair p = va_arg(ap, struct pair);
+    struct big b = va_arg(ap, struct big);
+    long double ld = va_arg(ap, long double);
+    char *s = va_arg(ap, char *);
+    va_end(ap);
+    return l + d + p.a + p.b + b.x + b.y + b.z + ld + s[1];
+}
+
+long pair_after(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    long s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + va_arg(ap, int);
+    struct pair p = va_arg(ap, struct pair);
+    va_end(ap);
+    return s + p.a + p.b * 4;
+}
+
+int vsum(int n, va_list ap) {
+    int s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + va_arg(ap, int);
+    return s;
+}
+
+int twice(int n, ...) {
+    va_list ap;
+    va_list aq;
+    va_start(ap, n);
+    va_copy(aq, ap);
+    int s = vsum(n, ap);
+    s = s + vsum(n, aq);
+    va_end(aq);
+    va_end(ap);
+    return s;
+}
+
+int main() {
+    char buf[64];
+    char c = 65;
+    float f = 1.5;
+    short sh = 7;
+    struct pair p = {3, 0.5};
+    struct big b = {4, 5, 6};
+    int r = 0;
+    sprintf(buf, "%d %c %.1f %s", sh, c, f, "ok");
+    r = r + (strcmp(buf, "7 A 1.5 ok") == 0);
+    r = r + sum_ints(3, 1, 2, 3);
+    r = r + sum_ints(10, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10);
+    r = r + sum_doubles(10, 0.5, 1.5, 2.5, 3.5, 4.5, 5.5, 6.5, 7.5, 8.5, 9.5);
+    r = r + sum_doubles(2, f, 1.0);
+    r = r + mixed(0, 1L, 2.5, p, b, 3.5L, "abc");
+    r = r + mixed(0, 1L, 2.5, p, b, 3.5L, "abc", 1, 2, 3, 4, 5, 6, 7, 8);
+    r = r + twice(3, 10, 20, 30);
+    r = r + pair_after(5, 1, 2, 3, 4, 5, p);
+    r = r + pair_after(1, 1, p);
+    return r;
+}
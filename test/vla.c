@@ -0,0 +1,58 @@
+int trace(int n, int a[n][n]) {
+    int s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + a[i][i];
+    return s;
+}
+
+long row_size(int m, int n, int a[m][n]) {
+    return sizeof(a[0]) + sizeof(*a) + (a[1] - a[0]);
+}
+
+int sum_proto(int n, int a[*]);
+
+int sum_proto(int n, int a[n]) {
+    int s = 0;
+    for (int i = 0; i < n; i = i + 1)
+        s = s + a[i];
+    return s;
+}
+
+int fill(int n) {
+    int a[n];
+    for (int i = 0; i < n; i = i + 1)
+        a[i] = i * 2;
+    return sum_proto(n, a) + sizeof(a);
+}
+
+int matrix(int n) {
+    int m[n][n];
+    for (int i = 0; i < n; i = i + 1)
+        for (int j = 0; j < n; j = j + 1)
+            m[i][j] = i * 10 + j;
+    int (*p)[n] = m;
+    p = p + 1;
+    return trace(n, m) + p[1][2] + (&m[n - 1] - &m[0]) + sizeof(m) / sizeof(m[0]);
+}
+
+int loop(int k) {
+    int s = 0;
+    for (int i = 1; i <= k; i = i + 1) {
+        char buf[i * 100];
+        buf[i * 100 - 1] = i;
+        s = s + buf[i * 100 - 1] + sizeof(buf) / 100;
+    }
+    return s;
+}
+
+int typedefs(int n) {
+    typedef int Row[n + 1];
+    n = 100;
+    Row r;
+    int fixed[3][n];
+    return sizeof(Row) + sizeof(r) + sizeof(fixed) / 100 + sizeof(int[n][2]);
+}
+
+int main() {
+    return fill(5) + matrix(3) + loop(20) + typedefs(2) + row_size(4, 3, 0);
+}
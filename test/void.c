@@ -0,0 +1,23 @@
+void set(int *p, int v) {
+    *p = v;
+    return;
+}
+
+void inc(int *p) {
+    *p = *p + 1;
+}
+
+void *id(void *p) {
+    return p;
+}
+
+int main(void) {
+    int a = 0;
+    set(&a, 5);
+    inc(&a);
+    int *p = id(&a);
+    void *q = p;
+    int *r = q;
+    *r = *r + 1;
+    return a;
+}
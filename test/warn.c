@@ -0,0 +1,71 @@
+#include <stdarg.h>
+
+int printf();
+
+int unused_param(int used, int unused) {
+    int unused_local;
+    return used * 2;
+}
+
+int sized(int n) {
+    int x;
+    return sizeof(x) + n;
+}
+
+int compare(int s, unsigned u) {
+    if (s < u)
+        return 1;
+    if (u < 10)
+        return 2;
+    return 3;
+}
+
+int no_return(int n) {
+    if (n)
+        return n + 1;
+}
+
+int cond(int n) {
+    int m;
+    if (m = n)
+        return m;
+    return 40;
+}
+
+int after_return(int n) {
+    return n * 3;
+    n = n + 1;
+}
+
+int shadow(int n) {
+    int k = n;
+    {
+        int k = 2;
+        n = n + k;
+    }
+    return n + k;
+}
+
+int qualifiers() {
+    const int c = 3;
+    int *p = &c;
+    return *p;
+}
+
+int excess() {
+    int a[2] = {1, 2, 3};
+    return a[0] + a[1];
+}
+
+int promoted(int n, ...) {
+    va_list ap;
+    va_start(ap, n);
+    n = va_arg(ap, short);
+    va_end(ap);
+    return n;
+}
+
+int main() {
+    int r = unused_param(5, 0) + sized(1) + compare(-1, 5) + compare(1, 20) + no_return(9) + cond(0) + cond(7);
+    return r + after_return(2) + shadow(1) + qualifiers() + excess() + abs(0 - 4);
+}
@@ -0,0 +1,17 @@
+int forty(int n) {
+    while (1) {
+        return n + 40;
+    }
+}
+
+int main() {
+    int i = 0;
+    int sum = 0;
+    while (i < 10) {
+        i = i + 1;
+        sum = sum + i;
+    }
+    while (0)
+        sum = 100;
+    return sum + forty(2);
+}